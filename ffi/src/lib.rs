@@ -0,0 +1,5 @@
+//! The C library for `enigma::ffi`, declared in `include/enigma.h`.
+
+extern crate enigma;
+
+pub use enigma::ffi::*;
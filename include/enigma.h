@@ -0,0 +1,96 @@
+/* Generated by cbindgen from src/ffi.rs. Do not edit. */
+
+#ifndef ENIGMA_H
+#define ENIGMA_H
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// The result of a call.
+typedef enum EnigmaStatus {
+  ENIGMA_STATUS_OK = 0,
+  ENIGMA_STATUS_NULL_POINTER = 1,
+  ENIGMA_STATUS_INVALID_UTF8 = 2,
+  ENIGMA_STATUS_INVALID_KEY = 3,
+  ENIGMA_STATUS_INVALID_MACHINE = 4,
+  ENIGMA_STATUS_INVALID_PLUGBOARD = 5,
+  ENIGMA_STATUS_INVALID_CHARACTER = 6,
+  ENIGMA_STATUS_BUFFER_TOO_SMALL = 7,
+} EnigmaStatus;
+
+// A machine, owned by the caller until it is passed to `enigma_free`.
+typedef struct EnigmaHandle EnigmaHandle;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Creates a machine from a key string, e.g. `"B II IV V / 02 21 12 / AV BS CG / BLA"`.
+//
+// On success, `*handle` is set to the new machine.
+//
+// # Safety
+//
+// `key` must be a NUL-terminated string, and `handle` must be valid to write to.
+enum EnigmaStatus enigma_new(const char *key, struct EnigmaHandle **handle);
+
+// Frees a machine created by `enigma_new`. Does nothing if `handle` is null.
+//
+// # Safety
+//
+// `handle` must have come from `enigma_new`, and must not be used afterwards.
+void enigma_free(struct EnigmaHandle *handle);
+
+// Presses a key, an ASCII letter in either case, and sets `*lamp` to the upper case letter which
+// lights up.
+//
+// # Safety
+//
+// `handle` must have come from `enigma_new`, and `lamp` must be valid to write to.
+enum EnigmaStatus enigma_press(struct EnigmaHandle *handle, char key, char *lamp);
+
+// Enciphers `len` bytes of `input` into `output`. Letters come out in upper case, and other
+// bytes are copied without pressing a key.
+//
+// `input` and `output` may be the same buffer.
+//
+// # Safety
+//
+// `handle` must have come from `enigma_new`, and both buffers must hold at least `len` bytes.
+enum EnigmaStatus enigma_encipher(struct EnigmaHandle *handle,
+                                  const char *input,
+                                  char *output,
+                                  size_t len);
+
+// Writes the letters showing in the windows, from left to right, as a NUL-terminated string.
+//
+// `len` is the size of `positions`, which needs a byte for each rotor and one for the NUL.
+//
+// # Safety
+//
+// `handle` must have come from `enigma_new`, and `positions` must hold at least `len` bytes.
+enum EnigmaStatus enigma_get_positions(const struct EnigmaHandle *handle,
+                                       char *positions,
+                                       size_t len);
+
+// Returns the rotors to the window positions in the key.
+//
+// # Safety
+//
+// `handle` must have come from `enigma_new`.
+enum EnigmaStatus enigma_reset(struct EnigmaHandle *handle);
+
+// A description of a status, as a static NUL-terminated string.
+//
+// The status is taken as a plain `int`, since C may pass any value: one which is not an
+// `EnigmaStatus` gives "unknown status".
+const char *enigma_status_message(int status);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* ENIGMA_H */
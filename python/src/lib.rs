@@ -0,0 +1,6 @@
+//! The Python extension module for `enigma::python`, built with `maturin build -m
+//! python/Cargo.toml`.
+
+extern crate enigma;
+
+pub use enigma::python::*;
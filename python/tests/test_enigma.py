@@ -0,0 +1,113 @@
+import pytest
+
+import enigma
+
+
+def test_message():
+    machine = enigma.Enigma(rotors="I II III", reflector="B")
+    assert machine.message("AAAAA") == "BDZGO"
+    assert machine.window_positions == "AAF"
+
+    machine.reset()
+    assert machine.message("Hello, world") == "ILBDA, AMTAZ"
+
+
+def test_window_positions():
+    machine = enigma.Enigma(rotors="I II III", reflector="B", window_positions="ADU")
+    machine.press("A")
+    assert machine.window_positions == "ADV"
+    machine.window_positions = "AAA"
+    assert machine.message("AAAAA") == "BDZGO"
+    with pytest.raises(ValueError):
+        machine.window_positions = "AA"
+
+
+def test_from_key():
+    key = "B I II III / 01 01 01 / AM FI / AAA"
+    machine = enigma.Enigma.from_key(key)
+    same = enigma.Enigma(rotors="I II III", reflector="B", plugboard="AM FI")
+    assert machine.message("THEQUICKBROWNFOX") == same.message("THEQUICKBROWNFOX")
+    with pytest.raises(ValueError):
+        enigma.Enigma.from_key("B I II / 01 01 01 / / AAA")
+
+
+def test_catalogues():
+    rotors = enigma.Rotors()
+    assert rotors.names() == ["I", "II", "III", "IV", "V"]
+    assert rotors["I"].wiring == "EKMFLGDQVZNTOWYHXUSPAIBRCJ"
+    assert rotors["V"].turnovers == "Z"
+    assert rotors.get("VI") is None
+    assert enigma.Reflectors()["B"].wiring == "YRUHQSLDPXNGOKMIEBFZCWVJAT"
+
+    custom = enigma.Rotor("EKMFLGDQVZNTOWYHXUSPAIBRCJ", "Q")
+    machine = enigma.Enigma(rotors=[custom, rotors["II"], rotors["III"]],
+                            reflector=enigma.Reflectors()["B"])
+    assert machine.message("AAAAA") == "BDZGO"
+
+
+def test_invalid_settings():
+    with pytest.raises(ValueError):
+        enigma.Enigma(rotors="I II VI", reflector="B")
+    with pytest.raises(ValueError):
+        enigma.Enigma(rotors="I II III", reflector="A")
+    with pytest.raises(ValueError):
+        enigma.Enigma(rotors="I II III", reflector="B", plugboard="AB AC")
+    with pytest.raises(ValueError):
+        enigma.Rotor("ABC", "Q")
+
+
+def test_scoring():
+    assert enigma.coincidences("ABC", "ABD") == 2
+    assert enigma.index_of_coincidence("AAAA") == 1.0
+    assert enigma.decibans(0.5, 0.5) == 0.0
+
+
+def test_banburismus():
+    machine = enigma.Enigma(rotors="I II III", reflector="B")
+    text = ("VONBEFEHLSHABERDERUBOOTEKEINEBESONDERENEREIGNISSEWETTERVORHERSAGEFUERDIEBISKAYA"
+            "WINDSUEDWESTSTAERKEVIERBISFUENFSEEGANGDREIBEDECKTREGENSICHTZEHNSEEMEILEN"
+            "GELEITZUGINQUADRATACHTSIEBENFUENFGEMELDETKURSNORDOSTFAHRTNEUNSEEMEILEN")
+    messages = []
+    for indicator, key in [("XYA", "AAC"), ("XYB", "AAF"), ("XYC", "AAJ"), ("XYD", "AAN")]:
+        machine.window_positions = key
+        messages.append((indicator, machine.message(text)))
+
+    evidence = enigma.right_wheel_evidence(messages)
+    assert evidence.ahead("A", "B", 3) > 0
+    alignments = enigma.chain(evidence, 5.0)
+    positions = dict(alignments[0].positions())
+    distance = lambda a, b: (ord(positions[b]) - ord(positions[a])) % 26
+    assert (distance("A", "B"), distance("A", "C"), distance("A", "D")) == (3, 7, 11)
+
+
+def test_rod():
+    k = enigma.Rotor("LPGSZMHAEOQKVXRFYBUTNICJDW", "Y")
+    machine = enigma.Enigma(rotors=[k, k, k], reflector="B")
+    plaintext = "KEINEBESONDERENEREIG"
+    ciphertext = machine.message(plaintext)
+    found = enigma.rod([enigma.Rotors()["I"], k], plaintext, ciphertext)
+    assert any(c.rotor == 1 and not c.turnovers for c in found)
+
+
+def test_recover_wiring():
+    rotors = enigma.Rotors()
+    machine = enigma.Enigma(rotors="I II III", reflector="B", ring_settings="AAC",
+                            plugboard="AM FI NV", window_positions="ADK")
+    observations = []
+    for i in range(300):
+        plaintext = "ABCDEFGHIJKLMNOPQRSTUVWXYZ"[i * 7 % 26]
+        ciphertext = machine.press(plaintext)
+        observations.append((machine.window_positions, plaintext, ciphertext))
+
+    known = [rotors["I"], rotors["II"], None]
+    wirings, exhaustive = enigma.recover_wiring(known, "AAC", "B", observations,
+                                                plugboard="AM FI NV")
+    assert wirings == [rotors["III"].wiring]
+    assert exhaustive
+
+    turnovers, _ = enigma.recover_turnovers(known, "AAC", "B", observations,
+                                            plugboard="AM FI NV")
+    assert turnovers == "V"
+
+    with pytest.raises(ValueError):
+        enigma.recover_wiring([], "", "B", observations)
@@ -1,5 +1,19 @@
+use core::convert::TryFrom;
+use core::fmt;
+use core::ops::Add;
+use core::ops::Sub;
+use core::str::FromStr;
 
-#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+#[cfg(feature = "alloc")]
+use alloc::string::String;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+/// A letter of the alphabet.
+///
+/// Letters can be added to and subtracted from one another, and to and from numbers, going round
+/// the alphabet like a rotor, e.g. `Alpha::Y + 3 == Alpha::B`.
+#[derive(Debug, Eq, PartialEq, Ord, PartialOrd, Hash, Clone, Copy)]
 pub enum Alpha {
     A = 0,
     B = 1,
@@ -69,6 +83,111 @@ impl From<Alpha> for char {
     }
 }
 
+impl fmt::Display for Alpha {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", char::from(*self))
+    }
+}
+
+/// Parses a single letter, in either case.
+impl FromStr for Alpha {
+    type Err = &'static str;
+
+    fn from_str(s: &str) -> Result<Alpha, &'static str> {
+        let mut chars = s.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Alpha::try_from_char(c),
+            _ => Err("Must be a single letter"),
+        }
+    }
+}
+
+impl TryFrom<char> for Alpha {
+    type Error = &'static str;
+
+    fn try_from(c: char) -> Result<Alpha, &'static str> {
+        Alpha::try_from_char(c)
+    }
+}
+
+/// Converts an ASCII letter, in either case.
+impl TryFrom<u8> for Alpha {
+    type Error = &'static str;
+
+    fn try_from(b: u8) -> Result<Alpha, &'static str> {
+        Alpha::try_from_char(char::from(b))
+    }
+}
+
+impl Add<usize> for Alpha {
+    type Output = Alpha;
+
+    fn add(self, n: usize) -> Alpha {
+        Alpha::try_from_usize((usize::from(self) + n % 26) % 26).unwrap()
+    }
+}
+
+impl Sub<usize> for Alpha {
+    type Output = Alpha;
+
+    fn sub(self, n: usize) -> Alpha {
+        Alpha::try_from_usize((usize::from(self) + 26 - n % 26) % 26).unwrap()
+    }
+}
+
+impl Add for Alpha {
+    type Output = Alpha;
+
+    fn add(self, other: Alpha) -> Alpha {
+        self + usize::from(other)
+    }
+}
+
+/// The distance from `other` forward to `self`, as a letter, e.g. `Alpha::C - Alpha::A == C`.
+impl Sub for Alpha {
+    type Output = Alpha;
+
+    fn sub(self, other: Alpha) -> Alpha {
+        self - usize::from(other)
+    }
+}
+
+/// What to do with characters which are not letters when converting a string.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum NonAlphabetic {
+    /// Leave them out, as `Alpha::from_string` does.
+    Drop,
+    /// Fail with the position of the first one.
+    Error,
+    /// Keep them where they are, so spacing and punctuation survive.
+    PassThrough,
+    /// Replace each with an `X`, as German operators did for punctuation.
+    ReplaceWithX,
+}
+
+/// A character of a string converted with `Alpha::from_string_with`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Symbol {
+    Letter(Alpha),
+    /// A character which is not a letter, kept by `NonAlphabetic::PassThrough`.
+    Other(char),
+}
+
+/// A character which is not a letter, found by `NonAlphabetic::Error`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct InvalidCharacter {
+    /// The index of the character in the string, counted in characters rather than bytes.
+    pub position: usize,
+    pub character: char,
+}
+
+impl fmt::Display for InvalidCharacter {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?} at position {} is not a letter", self.character, self.position)
+    }
+}
+
+#[cfg(feature = "alloc")]
 pub fn to_string(v: &[Alpha]) -> String {
     let mut cs = Vec::new();
     for c in v {
@@ -78,6 +197,12 @@ pub fn to_string(v: &[Alpha]) -> String {
 }
 
 impl Alpha {
+    /// Every letter, from `A` to `Z`.
+    pub fn all() -> impl Iterator<Item = Alpha> {
+        (0..26).map(|n| Alpha::try_from_usize(n).unwrap())
+    }
+
+    #[cfg(feature = "alloc")]
     pub fn from_string(s: &str) -> Vec<Alpha> {
         let mut v = Vec::new();
         for c in s.chars() {
@@ -88,9 +213,30 @@ impl Alpha {
         v
     }
 
+    /// Converts a string, handling characters which are not letters according to `policy`.
+    #[cfg(feature = "alloc")]
+    pub fn from_string_with(s: &str, policy: NonAlphabetic)
+                            -> Result<Vec<Symbol>, InvalidCharacter> {
+        let mut v = Vec::new();
+        for (position, c) in s.chars().enumerate() {
+            match (Alpha::try_from_char(c), policy) {
+                (Ok(a), _) => v.push(Symbol::Letter(a)),
+                (Err(_), NonAlphabetic::Drop) => {}
+                (Err(_), NonAlphabetic::Error) => {
+                    return Err(InvalidCharacter {
+                                   position,
+                                   character: c,
+                               })
+                }
+                (Err(_), NonAlphabetic::PassThrough) => v.push(Symbol::Other(c)),
+                (Err(_), NonAlphabetic::ReplaceWithX) => v.push(Symbol::Letter(Alpha::X)),
+            }
+        }
+        Ok(v)
+    }
+
     pub fn try_from_char(c: char) -> Result<Alpha, &'static str> {
         use alpha::Alpha::*;
-        use std::ascii::AsciiExt;
         match c.to_ascii_uppercase() {
             'A' => Ok(A),
             'B' => Ok(B),
@@ -155,3 +301,68 @@ impl Alpha {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+    use alloc::vec::Vec;
+    use alpha::Alpha::*;
+
+    #[test]
+    fn display_and_parse() {
+        assert_eq!("Q", Q.to_string());
+        assert_eq!(Ok(Q), "q".parse());
+        assert!("QR".parse::<Alpha>().is_err());
+        assert!("".parse::<Alpha>().is_err());
+        assert!("4".parse::<Alpha>().is_err());
+        assert_eq!(Ok(K), Alpha::try_from('k'));
+        assert_eq!(Ok(K), Alpha::try_from(b'K'));
+        assert!(Alpha::try_from(b'[').is_err());
+    }
+
+    #[test]
+    fn arithmetic() {
+        assert_eq!(D, A + 3);
+        assert_eq!(B, Y + 3);
+        assert_eq!(A, A + 26);
+        assert_eq!(Z, A - 1);
+        assert_eq!(X, B - 30);
+        assert_eq!(D, C + B);
+        assert_eq!(C, A - Y);
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn non_alphabetic_policies() {
+        let (h, i) = (Symbol::Letter(H), Symbol::Letter(I));
+
+        assert_eq!(Ok(vec![h, i]),
+                   Alpha::from_string_with("h, i", NonAlphabetic::Drop));
+        assert_eq!(Err(InvalidCharacter {
+                           position: 1,
+                           character: ',',
+                       }),
+                   Alpha::from_string_with("h, i", NonAlphabetic::Error));
+        assert_eq!(Ok(vec![h, Symbol::Other(','), Symbol::Other(' '), i]),
+                   Alpha::from_string_with("h, i", NonAlphabetic::PassThrough));
+        assert_eq!(Ok(vec![h, Symbol::Letter(X), Symbol::Letter(X), i]),
+                   Alpha::from_string_with("h, i", NonAlphabetic::ReplaceWithX));
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn invalid_character_position_counts_characters() {
+        let error = Alpha::from_string_with("ü!", NonAlphabetic::Error).unwrap_err();
+        assert_eq!(0, error.position);
+        assert_eq!("'ü' at position 0 is not a letter", error.to_string());
+    }
+
+    #[test]
+    fn all() {
+        let all: Vec<Alpha> = Alpha::all().collect();
+        assert_eq!(26, all.len());
+        assert_eq!((A, Z), (all[0], all[25]));
+        assert!(all.windows(2).all(|w| w[0] < w[1] && w[0] + 1 == w[1]));
+    }
+}
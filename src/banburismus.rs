@@ -0,0 +1,411 @@
+//! Banburismus: recovering the right and middle wheels from naval traffic.
+//!
+//! All messages for a day are sent with a message key which has been enciphered at the same
+//! Grundstellung to give the indicator. Each letter of the indicator is therefore a fixed,
+//! reciprocal substitution of the corresponding letter of the message key. Two messages whose
+//! indicators share their first two letters share the left and middle letters of their keys, so
+//! they are in depth once one is slid along the other by the (unknown) distance between the right
+//! letters of their keys.
+//!
+//! The procedure is:
+//!
+//! 1. Slide each such pair of messages against each other, counting repeats. Each offset gives a
+//!    'ban' of evidence, in decibans, that it is the distance between the two key letters.
+//!    See `right_wheel_evidence`.
+//! 2. Chain the evidence together to place the indicator letters relative to each other on the
+//!    alphabet. See `chain`.
+//! 3. Use the reciprocal nature of the indicator substitution to fix the chain to absolute
+//!    positions. See `Alignment::absolute`.
+//! 4. Find which wheel's turnover is consistent with the depths found. Passing a turnover between
+//!    two messages' key letters would move the middle wheel, so the turnover decides which of the
+//!    two messages started first. See `rank_wheels`.
+//!
+//! With the right wheel known, the same steps can be repeated for the middle letter of the
+//! indicator, using messages which only share their first letter. See `middle_wheel_evidence`.
+
+use alloc::vec::Vec;
+
+use alpha::Alpha;
+use rotor::RotorFactory;
+use scoring::coincidences;
+use scoring::decibans;
+
+/// A message with its three-letter indicator, after any bigram substitution has been removed.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Message {
+    pub indicator: [Alpha; 3],
+    pub text: Vec<Alpha>,
+}
+
+/// The statistics used to weigh evidence.
+#[derive(Debug, Copy, Clone)]
+pub struct Model {
+    /// The probability of a repeat between two messages in depth, around 1/17 for German naval
+    /// plaintext.
+    pub depth_repeat_rate: f64,
+    /// The probability of a repeat between two messages not in depth, 1/26.
+    pub random_repeat_rate: f64,
+    /// Overlaps shorter than this are ignored.
+    pub min_overlap: usize,
+}
+
+impl Default for Model {
+    fn default() -> Self {
+        Model {
+            depth_repeat_rate: 1.0 / 17.0,
+            random_repeat_rate: 1.0 / 26.0,
+            min_overlap: 20,
+        }
+    }
+}
+
+impl Model {
+    /// The evidence, in decibans, that `b` started `offset` letters later than `a` in the
+    /// machine's sequence of positions, i.e. that letter `i + offset` of `a` was enciphered at the
+    /// same position as letter `i` of `b`.
+    pub fn ban(&self, a: &[Alpha], b: &[Alpha], offset: usize) -> f64 {
+        if offset >= a.len() {
+            return 0.0;
+        }
+        let a = &a[offset..];
+        let overlap = a.len().min(b.len());
+        if overlap < self.min_overlap {
+            return 0.0;
+        }
+        let repeats = coincidences(a, b) as f64;
+        let repeat = decibans(self.depth_repeat_rate, self.random_repeat_rate);
+        let no_repeat = decibans(1.0 - self.depth_repeat_rate, 1.0 - self.random_repeat_rate);
+        repeats * repeat + (overlap as f64 - repeats) * no_repeat
+    }
+}
+
+/// The evidence, in decibans, for how far apart in the machine's sequence of positions the
+/// messages with each pair of indicator letters started.
+#[derive(Debug, Clone)]
+pub struct Evidence {
+    scores: Vec<f64>,
+}
+
+impl Default for Evidence {
+    fn default() -> Self {
+        Evidence { scores: vec![0.0; 26 * 26 * 26] }
+    }
+}
+
+impl Evidence {
+    /// The evidence that messages with indicator letter `to` started `distance` steps of the
+    /// wheel after messages with indicator letter `from`.
+    pub fn ahead(&self, from: Alpha, to: Alpha, distance: usize) -> f64 {
+        self.score(usize::from(from), usize::from(to), distance)
+    }
+
+    /// The evidence that the key letter behind `to` is `distance` places along the alphabet from
+    /// the key letter behind `from`, whichever message started first.
+    pub fn difference(&self, from: Alpha, to: Alpha, distance: usize) -> f64 {
+        self.difference_score(usize::from(from), usize::from(to), distance)
+    }
+
+    fn add(&mut self, from: usize, to: usize, distance: usize, score: f64) {
+        self.scores[Evidence::index(from, to, distance)] += score;
+    }
+
+    fn score(&self, from: usize, to: usize, distance: usize) -> f64 {
+        self.scores[Evidence::index(from, to, distance)]
+    }
+
+    fn difference_score(&self, from: usize, to: usize, distance: usize) -> f64 {
+        self.score(from, to, distance) + self.score(to, from, 26 - distance % 26)
+    }
+
+    fn index(from: usize, to: usize, distance: usize) -> usize {
+        (from * 26 + to) * 26 + distance % 26
+    }
+}
+
+/// Slides pairs of messages whose indicators share their first two letters against each other,
+/// collecting evidence for the distances between the right-hand letters of their keys.
+pub fn right_wheel_evidence(model: &Model, messages: &[Message]) -> Evidence {
+    let mut evidence = Evidence::default();
+    for (i, a) in messages.iter().enumerate() {
+        for b in &messages[i + 1..] {
+            let (p, q) = (usize::from(a.indicator[2]), usize::from(b.indicator[2]));
+            if a.indicator[..2] != b.indicator[..2] || p == q {
+                continue;
+            }
+            for distance in 1..26 {
+                evidence.add(p, q, distance, model.ban(&a.text, &b.text, distance));
+                evidence.add(q, p, distance, model.ban(&b.text, &a.text, distance));
+            }
+        }
+    }
+    evidence
+}
+
+/// Slides pairs of messages whose indicators share only their first letter against each other,
+/// collecting evidence for the distances between the middle letters of their keys.
+///
+/// This needs the right-hand letters of both keys, from an absolute alignment of the right wheel,
+/// and the turnover positions of the right wheel.
+pub fn middle_wheel_evidence(model: &Model,
+                             messages: &[Message],
+                             right: &Alignment,
+                             right_turnovers: &[Alpha])
+                             -> Evidence {
+    let mut evidence = Evidence::default();
+    for (i, a) in messages.iter().enumerate() {
+        for b in &messages[i + 1..] {
+            let (p, q) = (usize::from(a.indicator[1]), usize::from(b.indicator[1]));
+            if a.indicator[0] != b.indicator[0] || p == q {
+                continue;
+            }
+            let (ka, kb) = match (right.get(a.indicator[2]), right.get(b.indicator[2])) {
+                (Some(ka), Some(kb)) => (usize::from(ka), usize::from(kb)),
+                _ => continue,
+            };
+            for middle_distance in 1..26 {
+                let ahead = offset(ka, kb, middle_distance, right_turnovers);
+                evidence.add(p, q, middle_distance, model.ban(&a.text, &b.text, ahead));
+                let behind = offset(kb, ka, middle_distance, right_turnovers);
+                evidence.add(q, p, middle_distance, model.ban(&b.text, &a.text, behind));
+            }
+        }
+    }
+    evidence
+}
+
+/// How many key presses it takes to go from right-hand letter `from` to right-hand letter `to`,
+/// while moving the middle wheel on by `middle_distance`.
+///
+/// Each full turn of the right wheel moves the middle wheel on once, but so does passing a
+/// turnover on the way from `from` to `to`.
+fn offset(from: usize, to: usize, middle_distance: usize, right_turnovers: &[Alpha]) -> usize {
+    let right_distance = (to + 26 - from) % 26;
+    let turns = middle_distance - turnover_between(from, right_distance, right_turnovers) as usize;
+    right_distance + 26 * turns
+}
+
+/// Whether a wheel passes one of its turnovers when stepping `distance` times from `start`.
+fn turnover_between(start: usize, distance: usize, turnovers: &[Alpha]) -> bool {
+    turnovers.iter().any(|&t| (usize::from(t) + 26 - start) % 26 < distance)
+}
+
+/// Positions of indicator letters relative to each other on the alphabet, and the total evidence
+/// for them.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Alignment {
+    positions: [Option<Alpha>; 26],
+    pub score: f64,
+}
+
+impl Alignment {
+    /// The position of an indicator letter, if it is part of this alignment.
+    pub fn get(&self, letter: Alpha) -> Option<Alpha> {
+        self.positions[usize::from(letter)]
+    }
+
+    /// The indicator letters in this alignment.
+    pub fn letters(&self) -> Vec<Alpha> {
+        Alpha::all().filter(|&l| self.get(l).is_some()).collect()
+    }
+
+    /// The same alignment, moved `shift` places along the alphabet.
+    pub fn shifted(&self, shift: usize) -> Alignment {
+        let mut positions = [None; 26];
+        for (i, p) in self.positions.iter().enumerate() {
+            positions[i] = p.map(|p| p + shift);
+        }
+        Alignment {
+            positions,
+            score: self.score,
+        }
+    }
+
+    /// The shifts of this alignment which could be the true key letters behind each indicator
+    /// letter, as 'scritchmus' does.
+    ///
+    /// Enciphering the key is a reciprocal substitution without fixed points, so no letter may
+    /// be placed at itself, and if `x` is placed at `y` then `y` must be placed at `x`.
+    pub fn absolute(&self) -> Vec<Alignment> {
+        (0..26)
+            .map(|shift| self.shifted(shift))
+            .filter(|a| a.is_reciprocal())
+            .collect()
+    }
+
+    fn is_reciprocal(&self) -> bool {
+        for (i, p) in self.positions.iter().enumerate() {
+            if let Some(p) = *p {
+                let p = usize::from(p);
+                if p == i {
+                    return false;
+                }
+                if let Some(back) = self.positions[p] {
+                    if usize::from(back) != i {
+                        return false;
+                    }
+                }
+            }
+        }
+        true
+    }
+}
+
+/// Chains evidence together into alignments of indicator letters.
+///
+/// Starting from the strongest pair, letters are added one at a time at the position with the
+/// most evidence, as long as that evidence is at least `threshold` decibans. When no more letters
+/// can be added, a new chain is started from the strongest remaining pair. Chains are returned
+/// strongest first.
+pub fn chain(evidence: &Evidence, threshold: f64) -> Vec<Alignment> {
+    let mut placed = [false; 26];
+    let mut chains = Vec::new();
+    loop {
+        let mut best = None;
+        for p in 0..26 {
+            for q in 0..26 {
+                if p == q || placed[p] || placed[q] {
+                    continue;
+                }
+                for d in 1..26 {
+                    let score = evidence.difference_score(p, q, d);
+                    if score >= threshold && best.is_none_or(|(_, _, _, s)| score > s) {
+                        best = Some((p, q, d, score));
+                    }
+                }
+            }
+        }
+        let (p, q, d, score) = match best {
+            Some(b) => b,
+            None => break,
+        };
+
+        let mut positions = [None; 26];
+        positions[p] = Some(0);
+        positions[q] = Some(d);
+        placed[p] = true;
+        placed[q] = true;
+        let mut total = score;
+
+        while let Some((x, pos, score)) = best_extension(evidence, &positions, &placed) {
+            if score < threshold {
+                break;
+            }
+            positions[x] = Some(pos);
+            placed[x] = true;
+            total += score;
+        }
+
+        let mut alignment = [None; 26];
+        for (i, p) in positions.iter().enumerate() {
+            alignment[i] = p.map(|p| Alpha::try_from_usize(p).unwrap());
+        }
+        chains.push(Alignment {
+            positions: alignment,
+            score: total,
+        });
+    }
+    chains.sort_by(|a, b| b.score.total_cmp(&a.score));
+    chains
+}
+
+/// The unplaced letter and free position with the most evidence against the placed letters.
+fn best_extension(evidence: &Evidence,
+                  positions: &[Option<usize>; 26],
+                  placed: &[bool; 26])
+                  -> Option<(usize, usize, f64)> {
+    let mut taken = [false; 26];
+    for p in positions.iter().flatten() {
+        taken[*p] = true;
+    }
+    let mut best: Option<(usize, usize, f64)> = None;
+    for x in (0..26).filter(|&x| !placed[x]) {
+        for pos in (0..26).filter(|&pos| !taken[pos]) {
+            let score: f64 = positions.iter()
+                .enumerate()
+                .filter_map(|(y, p)| p.map(|p| evidence.difference_score(y, x, pos + 26 - p)))
+                .sum();
+            if best.is_none_or(|(_, _, s)| score > s) {
+                best = Some((x, pos, score));
+            }
+        }
+    }
+    best
+}
+
+/// How well each candidate wheel's turnovers fit an absolute alignment, best first, as pairs of
+/// the candidate's index and a score in decibans.
+///
+/// The turnovers decide which of two messages started first: stepping from one key letter to the
+/// other must not turn over the next wheel, or the messages would not be in depth. Each candidate
+/// is scored by the evidence for the depths its turnovers allow.
+pub fn rank_wheels(evidence: &Evidence,
+                   alignment: &Alignment,
+                   candidates: &[RotorFactory])
+                   -> Vec<(usize, f64)> {
+    let letters = alignment.letters();
+    let mut ranked: Vec<(usize, f64)> = candidates.iter()
+        .enumerate()
+        .map(|(i, candidate)| {
+            let turnovers = candidate.turnovers();
+            let mut score = 0.0;
+            for (j, &p) in letters.iter().enumerate() {
+                for &q in &letters[j + 1..] {
+                    let kp = usize::from(alignment.get(p).unwrap());
+                    let kq = usize::from(alignment.get(q).unwrap());
+                    let distance = (kq + 26 - kp) % 26;
+                    if !turnover_between(kp, distance, &turnovers) {
+                        score += evidence.ahead(p, q, distance);
+                    } else if !turnover_between(kq, 26 - distance, &turnovers) {
+                        score += evidence.ahead(q, p, 26 - distance);
+                    }
+                }
+            }
+            (i, score)
+        })
+        .collect();
+    ranked.sort_by(|a, b| b.1.total_cmp(&a.1));
+    ranked
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alpha::Alpha::*;
+
+    #[test]
+    fn ban_scores() {
+        let model = Model::default();
+        let text = Alpha::from_string("ABCDEFGHIJKLMNOPQRSTUVWXYZ");
+
+        assert!(model.ban(&text, &text, 0) > 0.0);
+        assert!(model.ban(&text, &text, 1) < 0.0);
+        assert_eq!(0.0, model.ban(&text, &text, 10));
+    }
+
+    #[test]
+    fn turnovers_between_letters() {
+        assert!(turnover_between(usize::from(P), 3, &[Q]));
+        assert!(!turnover_between(usize::from(R), 3, &[Q]));
+        assert!(turnover_between(usize::from(Y), 3, &[A]));
+        assert!(!turnover_between(usize::from(A), 0, &[A]));
+    }
+
+    #[test]
+    fn reciprocal_alignments() {
+        let mut positions = [None; 26];
+        positions[0] = Some(B);
+        positions[1] = Some(A);
+        let alignment = Alignment {
+            positions,
+            score: 0.0,
+        };
+
+        let absolute = alignment.absolute();
+        assert!(absolute.contains(&alignment));
+        // B would be placed at B
+        assert!(!absolute.contains(&alignment.shifted(1)));
+        // A would be placed at A
+        assert!(!absolute.contains(&alignment.shifted(25)));
+        assert_eq!(24, absolute.len());
+    }
+}
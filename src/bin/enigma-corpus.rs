@@ -0,0 +1,61 @@
+//! Writes a seeded corpus of enciphered traffic as JSON Lines, one message per line, with the
+//! ground truth for each message.
+//!
+//! `enigma-corpus PLAINTEXT DAYS [SEED]` sends ten messages a day on a random key sheet, taking
+//! the plaintext from the file `PLAINTEXT`, with the doubled indicator procedure and no errors.
+//! The same seed always gives the same corpus.
+
+extern crate enigma;
+
+use std::env;
+use std::fs;
+use std::io;
+use std::io::Write;
+use std::process;
+
+use enigma::corpus;
+use enigma::corpus::Corpus;
+
+const USAGE: &str = "Usage: enigma-corpus PLAINTEXT DAYS [SEED]";
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let (path, days, seed) = match parse_args(&args) {
+        Ok(a) => a,
+        Err(e) => {
+            eprintln!("{}", e);
+            eprintln!("{}", USAGE);
+            process::exit(2);
+        }
+    };
+    if let Err(e) = run(path, days, seed) {
+        eprintln!("enigma-corpus: {}", e);
+        process::exit(1);
+    }
+}
+
+fn parse_args(args: &[String]) -> Result<(&str, usize, u64), String> {
+    match args.len() {
+        2 | 3 => {}
+        _ => return Err("Wrong number of arguments".to_string()),
+    }
+    let days = args[1].parse().map_err(|_| format!("Invalid number of days: {}", args[1]))?;
+    let seed = match args.get(2) {
+        Some(s) => s.parse().map_err(|_| format!("Invalid seed: {}", s))?,
+        None => 0,
+    };
+    Ok((&args[0], days, seed))
+}
+
+fn run(path: &str, days: usize, seed: u64) -> io::Result<()> {
+    let source = fs::read_to_string(path)?;
+    let corpus = Corpus {
+        seed,
+        ..Corpus::default()
+    };
+    let records = corpus.generate(&corpus::key_sheet(days, seed), &source)
+        .map_err(|e| io::Error::other(e.to_string()))?;
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    out.write_all(corpus::json_lines(&records).as_bytes())
+}
@@ -0,0 +1,328 @@
+//! An interactive Enigma machine for the terminal.
+//!
+//! Letters typed on the keyboard light a lamp on the lampboard, and the rotor windows step as they
+//! would on the real machine. Plugboard pairs can be given as arguments, e.g.
+//! `enigma-tui AM FI NV PS TU WZ`.
+//!
+//! The terminal is put into raw mode using `stty`, so this needs a Unix-like terminal.
+
+extern crate enigma;
+
+use std::convert::TryFrom;
+use std::env;
+use std::io;
+use std::io::Read;
+use std::io::Write;
+use std::process;
+use std::process::Command;
+use std::process::Stdio;
+
+use enigma::Alpha;
+use enigma::Enigma;
+use enigma::EnigmaBuilder;
+use enigma::Plugboard;
+use enigma::Reflectors;
+use enigma::Rotors;
+
+const KEY_ROWS: [&str; 3] = ["QWERTZUIO", "ASDFGHJK", "PYXCVBNML"];
+
+const ESC: u8 = 27;
+const CTRL_C: u8 = 3;
+const CTRL_D: u8 = 4;
+const BACKSPACE: u8 = 127;
+const CTRL_H: u8 = 8;
+
+const HISTORY_LEN: usize = 40;
+
+/// Puts the terminal into raw mode, restoring the previous settings when dropped.
+struct RawMode {
+    saved: String,
+}
+
+impl RawMode {
+    fn enable() -> io::Result<RawMode> {
+        let output = Command::new("stty")
+            .arg("-g")
+            .stdin(Stdio::inherit())
+            .output()?;
+        if !output.status.success() {
+            return Err(io::Error::other("stdin is not a terminal"));
+        }
+        let saved = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        stty(&["raw", "-echo"])?;
+        Ok(RawMode { saved })
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        let _ = stty(&[&self.saved]);
+    }
+}
+
+fn stty(args: &[&str]) -> io::Result<()> {
+    Command::new("stty")
+        .args(args)
+        .stdin(Stdio::inherit())
+        .status()
+        .map(|_| ())
+}
+
+struct Screen {
+    positions: Vec<Alpha>,
+    pressed: Option<Alpha>,
+    lit: Option<Alpha>,
+    plugs: Vec<(Alpha, Alpha)>,
+    input: Vec<Alpha>,
+    output: Vec<Alpha>,
+    /// The window letters typed so far while setting the rotors directly.
+    setting: Option<Vec<Alpha>>,
+}
+
+impl Screen {
+    fn render(&self) -> String {
+        let mut s = String::new();
+        s.push_str("\x1b[H\x1b[2J");
+        s.push_str("  ENIGMA I   rotors I II III   reflector B\r\n\r\n");
+
+        s.push_str("  Rotors    ");
+        for &p in &self.positions {
+            s.push_str(&format!(" [ {} ]", char::from(p)));
+        }
+        s.push_str("\r\n\r\n  Lampboard\r\n");
+        s.push_str(&letter_rows(self.lit, "\x1b[1;30;43m"));
+        s.push_str("\r\n  Keyboard\r\n");
+        s.push_str(&letter_rows(self.pressed, "\x1b[7m"));
+
+        s.push_str("\r\n  Plugboard  ");
+        if self.plugs.is_empty() {
+            s.push_str("(no cables)");
+        }
+        for &(a, b) in &self.plugs {
+            s.push_str(&format!("{}{} ", char::from(a), char::from(b)));
+        }
+
+        s.push_str("\r\n\r\n  In   ");
+        s.push_str(&groups(&self.input));
+        s.push_str("\r\n  Out  ");
+        s.push_str(&groups(&self.output));
+
+        match self.setting {
+            Some(ref typed) => {
+                s.push_str("\r\n\r\n  Set rotors: ");
+                for i in 0..self.positions.len() {
+                    s.push(typed.get(i).map_or('_', |&l| char::from(l)));
+                }
+                s.push_str("   type the window letters from left to right   Esc: cancel\r\n");
+            }
+            None => {
+                s.push_str("\r\n\r\n  A-Z: press key   1/2/3: turn left/middle/right rotor   \
+                            4/5/6: turn it back\r\n  =: set rotors   0: reset   Esc: quit\r\n");
+            }
+        }
+        s
+    }
+}
+
+fn letter_rows(highlighted: Option<Alpha>, style: &str) -> String {
+    let mut s = String::new();
+    for (indent, row) in KEY_ROWS.iter().enumerate() {
+        s.push_str("    ");
+        for _ in 0..indent % 2 {
+            s.push_str("  ");
+        }
+        for c in row.chars() {
+            let letter = Alpha::try_from_char(c).unwrap();
+            if Some(letter) == highlighted {
+                s.push_str(&format!("{}({})\x1b[0m ", style, c));
+            } else {
+                s.push_str(&format!("({}) ", c));
+            }
+        }
+        s.push_str("\r\n");
+    }
+    s
+}
+
+fn groups(letters: &[Alpha]) -> String {
+    // Start on a whole group of five, so that the groups stay put as letters are added.
+    let extra = letters.len().saturating_sub(HISTORY_LEN);
+    let start = extra + (5 - extra % 5) % 5;
+    letters[start..]
+        .chunks(5)
+        .map(|group| group.iter().map(|&l| char::from(l)).collect())
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+fn parse_plugs(args: &[String]) -> Result<Plugboard, String> {
+    let mut pairs = Vec::new();
+    for arg in args {
+        let letters = Alpha::from_string(arg);
+        if letters.len() != 2 || letters.len() != arg.len() {
+            return Err(format!("Invalid plugboard pair: {}", arg));
+        }
+        pairs.push((letters[0], letters[1]));
+    }
+    Plugboard::new(&pairs).map_err(|e| e.to_string())
+}
+
+fn run(plugboard: Plugboard) -> io::Result<()> {
+    use enigma::Alpha::*;
+
+    let rotors = Rotors::default();
+    let reflectors = Reflectors::default();
+    let mut enigma = EnigmaBuilder::default()
+        .left_rotor(rotors.i.with_ring_setting(A))
+        .mid_rotor(rotors.ii.with_ring_setting(A))
+        .right_rotor(rotors.iii.with_ring_setting(A))
+        .window_positions([A, A, A])
+        .reflector(reflectors.b)
+        .plugboard(plugboard)
+        .build()
+        .map_err(io::Error::other)?;
+
+    let mut screen = Screen {
+        positions: enigma.get_window_positions(),
+        pressed: None,
+        lit: None,
+        plugs: plugboard.pairs(),
+        input: Vec::new(),
+        output: Vec::new(),
+        setting: None,
+    };
+
+    let _raw = RawMode::enable()?;
+    let stdin = io::stdin();
+    let stdout = io::stdout();
+    let mut out = stdout.lock();
+    out.write_all(screen.render().as_bytes())?;
+    out.flush()?;
+
+    let mut stdin = stdin.lock();
+    let mut buffer = [0; 64];
+    'keys: loop {
+        let read = stdin.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        let mut rest = &buffer[..read];
+        while !rest.is_empty() {
+            let (key, len) = next_key(rest);
+            rest = &rest[len..];
+            if let Some(byte) = key {
+                if !press(&mut enigma, &mut screen, byte) {
+                    break 'keys;
+                }
+            }
+        }
+        screen.positions = enigma.get_window_positions();
+        out.write_all(screen.render().as_bytes())?;
+        out.flush()?;
+    }
+    out.write_all(b"\r\n")?;
+    Ok(())
+}
+
+/// The first key in `bytes`, which were read together, and how many bytes it took.
+///
+/// Arrow and function keys send an escape sequence, which is read all at once and gives no key,
+/// so that only an Esc on its own gives `ESC`.
+fn next_key(bytes: &[u8]) -> (Option<u8>, usize) {
+    match bytes {
+        [ESC] => (Some(ESC), 1),
+        [ESC, b'[', rest @ ..] => {
+            // A control sequence ends with a byte from `@` to `~`.
+            let end = rest.iter().position(|b| (b'@'..=b'~').contains(b));
+            (None, 2 + end.map_or(rest.len(), |end| end + 1))
+        }
+        [ESC, b'O', _, ..] => (None, 3),
+        [ESC, _, ..] => (None, 2),
+        _ => (Some(bytes[0]), 1),
+    }
+}
+
+/// Acts on a key typed at the machine, returning false to quit.
+fn press(enigma: &mut Enigma, screen: &mut Screen, byte: u8) -> bool {
+    screen.pressed = None;
+    screen.lit = None;
+    if let Some(mut typed) = screen.setting.take() {
+        match byte {
+            CTRL_C | CTRL_D => return false,
+            ESC => {}
+            BACKSPACE | CTRL_H => {
+                typed.pop();
+                screen.setting = Some(typed);
+            }
+            _ => {
+                if let Ok(letter) = Alpha::try_from(byte) {
+                    typed.push(letter);
+                }
+                if typed.len() == screen.positions.len() {
+                    enigma.set_window_positions(&typed);
+                } else {
+                    screen.setting = Some(typed);
+                }
+            }
+        }
+        return true;
+    }
+    match byte {
+        ESC | CTRL_C | CTRL_D => return false,
+        b'0' => enigma.reset(),
+        b'=' => screen.setting = Some(Vec::new()),
+        b'1'..=b'6' => {
+            let slot = usize::from(byte - b'1') % 3;
+            let mut positions = enigma.get_window_positions();
+            positions[slot] = if byte <= b'3' {
+                positions[slot] + 1
+            } else {
+                positions[slot] - 1
+            };
+            enigma.set_window_positions(&positions);
+        }
+        _ => {
+            if let Ok(letter) = Alpha::try_from(byte) {
+                let lamp = enigma.press(letter);
+                screen.pressed = Some(letter);
+                screen.lit = Some(lamp);
+                screen.input.push(letter);
+                screen.output.push(lamp);
+            }
+        }
+    }
+    true
+}
+
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let plugboard = match parse_plugs(&args) {
+        Ok(p) => p,
+        Err(e) => {
+            eprintln!("{}", e);
+            eprintln!("Usage: enigma-tui [PAIR...]   e.g. enigma-tui AM FI NV");
+            process::exit(2);
+        }
+    };
+    if let Err(e) = run(plugboard) {
+        eprintln!("enigma-tui: {}", e);
+        process::exit(1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escape_sequences_are_not_keys() {
+        assert_eq!((Some(ESC), 1), next_key(&[ESC]));
+        assert_eq!((Some(b'a'), 1), next_key(b"ab"));
+        // Up arrow, then a letter typed quickly after it.
+        assert_eq!((None, 3), next_key(b"\x1b[Aq"));
+        // F5 and Alt-q.
+        assert_eq!((None, 5), next_key(b"\x1b[15~"));
+        assert_eq!((None, 2), next_key(b"\x1bq"));
+        assert_eq!((None, 3), next_key(b"\x1bOP"));
+    }
+}
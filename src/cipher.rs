@@ -10,7 +10,6 @@ pub type CipherChar = usize;
 const ASCII_UPPERCASE_A: usize = 65;
 
 pub fn from_char(c: char) -> CipherChar {
-    use std::ascii::AsciiExt;
     assert!(c.is_ascii() && c.is_alphabetic(),
             "Char must be ascii and alphabetic");
 
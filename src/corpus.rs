@@ -0,0 +1,369 @@
+//! Generating corpora of traffic, many days long, for benchmarking the attacks.
+//!
+//! A key sheet gives one key string per day (see `key`). Each day, a `traffic::Generator` sends a
+//! number of messages on that day's key, following the indicator procedure, with plaintext taken
+//! from a source text. Everything is chosen from a seed, so a corpus can be made again exactly.
+//!
+//! `json_lines` writes a corpus out with one message per line, for example (wrapped here):
+//!
+//! ```text
+//! {"day":1,"number":1,"key":"B II IV V / 02 21 12 / AV BS CG DL FU HZ IN KM OW RX / BLA",
+//!  "grundstellung":null,"indicator":"PEZUOH","ciphertext":"...","message_key":"ABC",
+//!  "start_positions":"ABC","plaintext":"...","clean_ciphertext":"...","errors":[]}
+//! ```
+//!
+//! `key` is the day's key, and the fields from `message_key` on are the ground truth. Each error
+//! is an object with a `kind`, and for transmission errors the `position` in the clean ciphertext
+//! and the letter it `was`.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+use core::fmt::Write;
+
+use alpha::Alpha;
+use alpha::to_string;
+use enigma::EnigmaBuilder;
+use error::Error;
+use key;
+use model::MachineModel;
+use model::WheelSpec;
+use plugboard::Plugboard;
+use random::Rng;
+use rotor::Rotor;
+use traffic::ErrorModel;
+use traffic::Generator;
+use traffic::Message;
+use traffic::OperatorError;
+use traffic::Procedure;
+
+/// How much traffic to send each day, and how.
+#[derive(Debug, Copy, Clone)]
+pub struct Corpus {
+    pub messages_per_day: usize,
+    /// The shortest message, in letters.
+    pub min_length: usize,
+    /// The longest message, in letters.
+    pub max_length: usize,
+    pub procedure: Procedure,
+    pub errors: ErrorModel,
+    /// The seed for every random choice, so that a corpus can be made again exactly.
+    pub seed: u64,
+}
+
+/// A message in a corpus, with the day's key it was sent on.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Record {
+    /// The day, counting from 1.
+    pub day: usize,
+    /// The message's number within the day, counting from 1.
+    pub number: usize,
+    pub key: String,
+    pub message: Message,
+}
+
+/// Why a corpus could not be generated.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum CorpusError {
+    /// The shortest message is longer than the longest.
+    Lengths,
+    /// The plaintext source has no letters.
+    Source,
+    /// A key on the key sheet does not describe a machine.
+    Key {
+        /// The day of the key, counting from 1.
+        day: usize,
+        error: Error,
+    },
+}
+
+impl fmt::Display for CorpusError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CorpusError::Lengths => {
+                write!(f, "Shortest message must not be longer than the longest")
+            }
+            CorpusError::Source => write!(f, "Plaintext source must contain letters"),
+            CorpusError::Key { day, error } => write!(f, "Day {}: {}", day, error),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl ::std::error::Error for CorpusError {}
+
+impl Default for Corpus {
+    fn default() -> Self {
+        Corpus {
+            messages_per_day: 10,
+            min_length: 50,
+            max_length: 250,
+            procedure: Procedure::Doubled,
+            errors: ErrorModel::default(),
+            seed: 0,
+        }
+    }
+}
+
+impl Corpus {
+    /// Sends a day's traffic for each key in `key_sheet`, taking the plaintext of each message
+    /// from a random place in the letters of `source`, wrapping round at the end.
+    pub fn generate(&self, key_sheet: &[String], source: &str)
+                    -> Result<Vec<Record>, CorpusError> {
+        if self.min_length > self.max_length {
+            return Err(CorpusError::Lengths);
+        }
+        let source = Alpha::from_string(source);
+        if source.is_empty() {
+            return Err(CorpusError::Source);
+        }
+        let mut rng = Rng::new(self.seed);
+        let mut records = Vec::with_capacity(key_sheet.len() * self.messages_per_day);
+        for (day, key) in key_sheet.iter().enumerate() {
+            let error = |error| CorpusError::Key { day: day + 1, error };
+            let enigma = key::parse(key).map_err(error)?
+                .build()
+                .map_err(|e| error(Error::Machine(e)))?;
+            let mut generator = Generator::new(enigma, self.procedure, self.errors, rng.next_u64());
+            for number in 0..self.messages_per_day {
+                let length = self.min_length + rng.below(self.max_length - self.min_length + 1);
+                let start = rng.below(source.len());
+                let plaintext: Vec<Alpha> =
+                    source.iter().cycle().skip(start).take(length).cloned().collect();
+                records.push(Record {
+                                 day: day + 1,
+                                 number: number + 1,
+                                 key: key.clone(),
+                                 message: generator.message(&plaintext),
+                             });
+            }
+        }
+        Ok(records)
+    }
+}
+
+/// A key sheet for `days` days, of Enigma I keys chosen at random: reflector B, three of rotors
+/// I to V, ten plugboard pairs and, for the doubled indicator procedure, a Grundstellung.
+pub fn key_sheet(days: usize, seed: u64) -> Vec<String> {
+    let mut rng = Rng::new(seed);
+    let model = MachineModel::EnigmaI;
+    (0..days)
+        .map(|_| {
+            let mut specs: Vec<&WheelSpec> = model.rotors().iter().collect();
+            let specs: Vec<&WheelSpec> =
+                (0..3).map(|_| specs.remove(rng.below(specs.len()))).collect();
+            let rotors: Vec<Rotor> = specs.iter()
+                .map(|spec| spec.factory().with_ring_setting(Alpha::A + rng.below(26)))
+                .collect();
+            let mut letters: Vec<Alpha> = Alpha::all().collect();
+            let pairs: Vec<(Alpha, Alpha)> = (0..10)
+                .map(|_| {
+                    let a = letters.remove(rng.below(letters.len()));
+                    (a, letters.remove(rng.below(letters.len())))
+                })
+                .collect();
+            let windows: Vec<Alpha> = (0..3).map(|_| Alpha::A + rng.below(26)).collect();
+            let enigma = EnigmaBuilder::for_model(model)
+                .reflector(model.reflector("B").unwrap())
+                .rotors(rotors)
+                .plugboard(Plugboard::new(&pairs).unwrap())
+                .window_positions(windows)
+                .build()
+                .unwrap();
+            key::format(&enigma).unwrap()
+        })
+        .collect()
+}
+
+impl Record {
+    /// The record as a JSON object, on one line.
+    pub fn to_json(&self) -> String {
+        let message = &self.message;
+        let truth = &message.truth;
+        let mut json = String::new();
+        write!(json, "{{\"day\":{},\"number\":{},\"key\":", self.day, self.number).unwrap();
+        push_string(&mut json, &self.key);
+        json.push_str(",\"grundstellung\":");
+        match message.grundstellung {
+            Some(ref g) => push_string(&mut json, &to_string(g)),
+            None => json.push_str("null"),
+        }
+        let fields = [("indicator", &message.indicator),
+                      ("ciphertext", &message.ciphertext),
+                      ("message_key", &truth.message_key),
+                      ("start_positions", &truth.start_positions),
+                      ("plaintext", &truth.plaintext),
+                      ("clean_ciphertext", &truth.ciphertext)];
+        for &(name, letters) in &fields {
+            write!(json, ",\"{}\":", name).unwrap();
+            push_string(&mut json, &to_string(letters));
+        }
+        json.push_str(",\"errors\":[");
+        for (i, error) in truth.errors.iter().enumerate() {
+            if i > 0 {
+                json.push(',');
+            }
+            match *error {
+                OperatorError::WrongPositions => json.push_str("{\"kind\":\"wrong_positions\"}"),
+                OperatorError::UndoubledIndicator => {
+                    json.push_str("{\"kind\":\"undoubled_indicator\"}")
+                }
+                OperatorError::ReusedKey => json.push_str("{\"kind\":\"reused_key\"}"),
+                OperatorError::Garbled { position, was } => {
+                    write!(json, "{{\"kind\":\"garbled\",\"position\":{},\"was\":\"{}\"}}",
+                           position, was)
+                        .unwrap()
+                }
+                OperatorError::Dropped { position, was } => {
+                    write!(json, "{{\"kind\":\"dropped\",\"position\":{},\"was\":\"{}\"}}",
+                           position, was)
+                        .unwrap()
+                }
+            }
+        }
+        json.push_str("]}");
+        json
+    }
+}
+
+/// The records as JSON Lines: one JSON object per line, each line ending in a newline.
+pub fn json_lines(records: &[Record]) -> String {
+    let mut lines = String::new();
+    for record in records {
+        lines.push_str(&record.to_json());
+        lines.push('\n');
+    }
+    lines
+}
+
+/// Writes `s` as a JSON string.
+fn push_string(json: &mut String, s: &str) {
+    json.push('"');
+    for c in s.chars() {
+        match c {
+            '"' => json.push_str("\\\""),
+            '\\' => json.push_str("\\\\"),
+            c if (c as u32) < 0x20 => write!(json, "\\u{:04x}", c as u32).unwrap(),
+            c => json.push(c),
+        }
+    }
+    json.push('"');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alloc::string::ToString;
+
+    const SOURCE: &str = "An das Oberkommando der Wehrmacht: Keine besonderen Ereignisse. \
+                          Feind ruhig, Nachschub eingetroffen.";
+
+    #[test]
+    fn key_sheets_parse() {
+        let sheet = key_sheet(30, 1);
+        assert_eq!(30, sheet.len());
+        for key in &sheet {
+            let enigma = key::parse(key).unwrap().build().unwrap();
+            assert_eq!(10, enigma.plugboard().pairs().len());
+            assert_eq!(*key, key::format(&enigma).unwrap());
+        }
+        assert_eq!(sheet, key_sheet(30, 1));
+        assert_ne!(sheet, key_sheet(30, 2));
+    }
+
+    #[test]
+    fn generates_days_of_traffic() {
+        let corpus = Corpus {
+            messages_per_day: 4,
+            min_length: 20,
+            max_length: 40,
+            ..Corpus::default()
+        };
+        let sheet = key_sheet(3, 7);
+        let records = corpus.generate(&sheet, SOURCE).unwrap();
+        assert_eq!(12, records.len());
+        assert_eq!((3, 4), (records[11].day, records[11].number));
+        assert_eq!(sheet[1], records[4].key);
+        for record in &records {
+            let truth = &record.message.truth;
+            assert!(truth.plaintext.len() >= 20 && truth.plaintext.len() <= 40);
+            assert_eq!(6, record.message.indicator.len());
+
+            // The day's key and the indicator are enough to read the message.
+            let mut enigma = key::parse(&record.key).unwrap().build().unwrap();
+            let message_key = enigma.message(&record.message.indicator)[..3].to_vec();
+            assert_eq!(truth.message_key, message_key);
+            enigma.set_window_positions(&message_key);
+            assert_eq!(truth.plaintext, enigma.message(&record.message.ciphertext));
+        }
+        assert_eq!(records, corpus.generate(&sheet, SOURCE).unwrap());
+    }
+
+    #[test]
+    fn invalid_input() {
+        let corpus = Corpus::default();
+        assert_eq!(Err(CorpusError::Source), corpus.generate(&key_sheet(1, 0), "1234"));
+        let sheet = [key_sheet(1, 0)[0].clone(), "B I II".to_string()];
+        match corpus.generate(&sheet, SOURCE) {
+            Err(CorpusError::Key { day: 2, error: Error::Key(_) }) => {}
+            other => panic!("{:?}", other),
+        }
+        let backwards = Corpus {
+            min_length: 10,
+            max_length: 5,
+            ..Corpus::default()
+        };
+        assert_eq!(Err(CorpusError::Lengths), backwards.generate(&key_sheet(1, 0), SOURCE));
+    }
+
+    #[test]
+    fn writes_json_lines() {
+        let errors = ErrorModel {
+            reused_key: 1.0,
+            garbled: 1.0,
+            ..ErrorModel::default()
+        };
+        let corpus = Corpus {
+            messages_per_day: 2,
+            min_length: 2,
+            max_length: 2,
+            procedure: Procedure::Single,
+            errors,
+            seed: 3,
+        };
+        let key = "B I II III / 01 01 01 / AM FI NV PS TU WZ / FOL".to_string();
+        let records = corpus.generate(&[key], SOURCE).unwrap();
+        let lines = json_lines(&records);
+        assert_eq!(2, lines.lines().count());
+        assert!(lines.ends_with("}\n"));
+
+        let m = &records[1].message;
+        let t = &m.truth;
+        let expected = format!("{{\"day\":1,\"number\":2,\
+                                \"key\":\"B I II III / 01 01 01 / AM FI NV PS TU WZ / FOL\",\
+                                \"grundstellung\":\"{}\",\"indicator\":\"{}\",\
+                                \"ciphertext\":\"{}\",\"message_key\":\"{}\",\
+                                \"start_positions\":\"{}\",\"plaintext\":\"{}\",\
+                                \"clean_ciphertext\":\"{}\",\"errors\":[\
+                                {{\"kind\":\"reused_key\"}},\
+                                {{\"kind\":\"garbled\",\"position\":0,\"was\":\"{}\"}},\
+                                {{\"kind\":\"garbled\",\"position\":1,\"was\":\"{}\"}}]}}",
+                               to_string(m.grundstellung.as_ref().unwrap()),
+                               to_string(&m.indicator),
+                               to_string(&m.ciphertext),
+                               to_string(&t.message_key),
+                               to_string(&t.start_positions),
+                               to_string(&t.plaintext),
+                               to_string(&t.ciphertext),
+                               t.ciphertext[0],
+                               t.ciphertext[1]);
+        assert_eq!(expected, records[1].to_json());
+    }
+
+    #[test]
+    fn escapes_strings() {
+        let mut json = String::new();
+        push_string(&mut json, "a\"b\\c\n");
+        assert_eq!("\"a\\\"b\\\\c\\u000a\"", json);
+    }
+}
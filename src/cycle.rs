@@ -0,0 +1,26 @@
+use alloc::vec::Vec;
+
+use alpha::Alpha;
+
+/// The path the rotors take as keys are pressed, found by `Enigma::cycle`.
+///
+/// Because of double stepping, some window positions can never be reached by pressing keys. A
+/// machine started at one of these passes through a few of them (the transient) before joining
+/// the cycle which it then repeats forever.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Cycle {
+    /// The number of key presses before the machine enters the cycle.
+    pub transient: usize,
+    /// The number of key presses after which the window positions repeat.
+    pub period: usize,
+    /// Every window position reached, in order, starting with the positions the walk began at.
+    /// The cycle is `positions[transient..]`.
+    pub positions: Vec<Vec<Alpha>>,
+}
+
+impl Cycle {
+    /// The window positions which recur as the machine is used.
+    pub fn cycle(&self) -> &[Vec<Alpha>] {
+        &self.positions[self.transient..]
+    }
+}
@@ -1,15 +1,136 @@
+use core::ops::Index;
+use core::ops::IndexMut;
+
 use rotor::Rotor;
 use reflector::Reflector;
+use plugboard::Plugboard;
+use entry_wheel::EntryWheel;
+use model::MachineModel;
+use model::Stepping;
+use model::WheelSpec;
+use observer::Event;
+use observer::Observer;
+use observer::Unobserved;
+use state;
+use state::MachineState;
+use state::Settings;
+use trace::Component;
+#[cfg(feature = "alloc")]
+use trace::Hop;
+#[cfg(feature = "alloc")]
+use trace::Trace;
+#[cfg(feature = "alloc")]
+use cycle::Cycle;
+
+#[cfg(feature = "alloc")]
+use alloc::collections::BTreeMap;
+#[cfg(feature = "alloc")]
+use alloc::string::String;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
 
 use alpha::Alpha;
+#[cfg(feature = "alloc")]
+use alpha::InvalidCharacter;
+#[cfg(feature = "alloc")]
+use alpha::NonAlphabetic;
+#[cfg(feature = "alloc")]
+use alpha::Symbol;
 use cipher::CipherChar;
 
+/// The most rotors a machine can hold, including fixed wheels and stators.
+///
+/// The machine holds its rotors inline so that it never allocates, which puts a limit on how many
+/// it can have. Eight is enough for every machine in the catalogue. `EnigmaBuilder::build` fails
+/// with "Too many rotors" if given more, and the message names this number.
+pub const MAX_ROTORS: usize = 8;
+
+/// An Enigma machine with up to `MAX_ROTORS` rotors.
+///
+/// The rotors are held from left to right. Some may be fixed wheels which never step: to the left
+/// of the stepping rotors, such as the Greek wheel of the M4, or to the right, such as the stators
+/// of the Typex. The remaining rotors step as a key is pressed: the right-most on every key press,
+/// and each of the others when the rotor to its right turns over.
+///
+/// The machine is held entirely inline and never allocates, so it can be used without `alloc`.
 #[derive(Debug, Copy, Clone)]
 pub struct Enigma {
-    rotors: [RR; 3], // from left to right
+    rotors: Slots<RR>, // from left to right
     reflector: Reflector,
+    plugboard: Plugboard,
+    entry_wheel: Option<EntryWheel>,
+    stepping: Stepping,
+    reflector_position: CipherChar,
+
+    original_window_positions: Slots<CipherChar>,
+    original_reflector_position: CipherChar,
+}
+
+/// A list of up to `MAX_ROTORS` items, held inline.
+#[derive(Debug, Copy, Clone)]
+struct Slots<T: Copy> {
+    items: [Option<T>; MAX_ROTORS],
+    len: usize,
+}
+
+impl<T: Copy> Slots<T> {
+    fn from_slice(items: &[T]) -> Result<Slots<T>, &'static str> {
+        let mut slots = Slots::default();
+        for &item in items {
+            slots.push(item)?;
+        }
+        Ok(slots)
+    }
+
+    fn push(&mut self, item: T) -> Result<(), &'static str> {
+        if self.len == MAX_ROTORS {
+            return Err("Too many rotors: at most MAX_ROTORS (8), counting fixed wheels");
+        }
+        self.items[self.len] = Some(item);
+        self.len += 1;
+        Ok(())
+    }
+
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn iter<'a>(&'a self) -> impl DoubleEndedIterator<Item = &'a T> + ExactSizeIterator + 'a {
+        self.items[..self.len].iter().map(|item| item.as_ref().unwrap())
+    }
+
+    fn iter_mut<'a>(&'a mut self) -> impl Iterator<Item = &'a mut T> + 'a {
+        self.items[..self.len].iter_mut().map(|item| item.as_mut().unwrap())
+    }
+}
 
-    original_window_positions: [CipherChar; 3],
+impl<T: Copy> Default for Slots<T> {
+    fn default() -> Slots<T> {
+        Slots {
+            items: [None; MAX_ROTORS],
+            len: 0,
+        }
+    }
+}
+
+impl<T: Copy> Index<usize> for Slots<T> {
+    type Output = T;
+
+    fn index(&self, i: usize) -> &T {
+        assert!(i < self.len, "No rotor in slot {}", i);
+        self.items[i].as_ref().unwrap()
+    }
+}
+
+impl<T: Copy> IndexMut<usize> for Slots<T> {
+    fn index_mut(&mut self, i: usize) -> &mut T {
+        assert!(i < self.len, "No rotor in slot {}", i);
+        self.items[i].as_mut().unwrap()
+    }
 }
 
 /// This struct tracks the rotation of a Rotor within the Enigma machine.
@@ -17,9 +138,41 @@ pub struct Enigma {
 struct RR {
     pub rotor: Rotor,
     pub window_position: CipherChar,
+    pub steps: bool,
+}
+
+impl RR {
+    fn new(rotor: Rotor, window_letter: CipherChar, steps: bool) -> RR {
+        RR {
+            rotor,
+            window_position: RR::letter_to_position(rotor, window_letter),
+            steps,
+        }
+    }
+
+    /// The letter showing in the window. A reversed rotor's ring reads backwards, so this runs in
+    /// the opposite direction to its rotation.
+    fn window_letter(&self) -> CipherChar {
+        RR::letter_to_position(self.rotor, self.window_position)
+    }
+
+    fn letter_to_position(rotor: Rotor, letter: CipherChar) -> CipherChar {
+        if rotor.is_reversed() {
+            (26 - letter) % 26
+        } else {
+            letter
+        }
+    }
+
+    /// How far the wiring core is turned from its starting contacts, taking the ring setting into
+    /// account.
+    fn offset(&self) -> CipherChar {
+        self.rotor.core_offset(self.window_position)
+    }
 }
 
 impl Enigma {
+    #[cfg(feature = "alloc")]
     pub fn message(&mut self, cipher_text: &[Alpha]) -> Vec<Alpha> {
         let mut v = Vec::new();
         for l in cipher_text {
@@ -28,90 +181,392 @@ impl Enigma {
         v
     }
 
+    /// Enciphers `input` into `output`, without allocating.
+    ///
+    /// Panics if the slices are different lengths.
+    pub fn encipher_into(&mut self, input: &[Alpha], output: &mut [Alpha]) {
+        assert_eq!(input.len(), output.len(), "Output must be the same length as input");
+        for (&l, out) in input.iter().zip(output.iter_mut()) {
+            *out = self.press(l);
+        }
+    }
+
+    /// Enciphers `letters`, replacing each with its cipher letter.
+    pub fn encipher_in_place(&mut self, letters: &mut [Alpha]) {
+        for l in letters.iter_mut() {
+            *l = self.press(*l);
+        }
+    }
+
+    /// Enciphers a string, handling characters which are not letters according to `policy`.
+    ///
+    /// Letters come out in upper case. With `NonAlphabetic::PassThrough`, other characters are
+    /// copied to the output without pressing a key, so spacing and punctuation survive the round
+    /// trip.
+    #[cfg(feature = "alloc")]
+    pub fn encipher_str(&mut self, text: &str, policy: NonAlphabetic)
+                        -> Result<String, InvalidCharacter> {
+        let symbols = Alpha::from_string_with(text, policy)?;
+        Ok(symbols.into_iter()
+               .map(|s| match s {
+                        Symbol::Letter(l) => char::from(self.press(l)),
+                        Symbol::Other(c) => c,
+                    })
+               .collect())
+    }
+
     pub fn press(&mut self, letter: Alpha) -> Alpha {
-        self.step_and_turnover();
+        self.press_observed(letter, &mut Unobserved)
+    }
+
+    /// Presses a key, telling `observer` how the rotors move and which lamp lights.
+    pub fn press_observed<O: Observer>(&mut self, letter: Alpha, observer: &mut O) -> Alpha {
+        self.step_and_turnover(observer);
         let result = self.encipher(CipherChar::from(letter));
-        Alpha::try_from_usize(result).unwrap()
+        let lamp = Alpha::try_from_usize(result).unwrap();
+        observer.notify(Event::Lamp { key: letter, lamp });
+        lamp
     }
 
     pub fn reset(&mut self) {
-        for (i, pos) in self.original_window_positions.iter().enumerate() {
-            self.rotors[i].window_position = *pos;
+        self.reset_observed(&mut Unobserved)
+    }
+
+    /// Like `reset`, but tells `observer`.
+    pub fn reset_observed<O: Observer>(&mut self, observer: &mut O) {
+        for (rr, &pos) in self.rotors.iter_mut().zip(self.original_window_positions.iter()) {
+            rr.window_position = RR::letter_to_position(rr.rotor, pos);
         }
+        self.reflector_position = self.original_reflector_position;
+        observer.notify(Event::Reset);
     }
 
-    pub fn get_window_positions(&self) -> [Alpha; 3] {
-        let mut positions = [Alpha::A; 3];
-        for (i, rr) in self.rotors.iter().enumerate() {
-            positions[i] = Alpha::try_from_usize(rr.window_position).unwrap();
+    /// Takes a checkpoint of the window positions and the reflector position, which `restore` can
+    /// return to later, e.g. part way through a message. The checkpoint records the machine's
+    /// settings too, so that it cannot be restored to a machine set up differently.
+    pub fn snapshot(&self) -> MachineState {
+        let mut positions = [Alpha::A; MAX_ROTORS];
+        let mut rings = [Alpha::A; MAX_ROTORS];
+        let mut wheels = state::FINGERPRINT;
+        for (slot, rr) in self.rotors.iter().enumerate() {
+            positions[slot] = self.window_position(slot);
+            rings[slot] = Alpha::try_from_usize(rr.rotor.ring_setting()).unwrap();
+            wheels = state::fingerprint(rr.rotor.fingerprint(wheels), Some(rr.steps as usize));
+        }
+        if let Some(entry_wheel) = self.entry_wheel {
+            wheels = entry_wheel.fingerprint(wheels);
+        }
+        let stepping = match self.stepping {
+            Stepping::Ratchet => 0,
+            Stepping::Gear => 1,
+        };
+        let settings = Settings {
+            ring_settings: &rings[..self.rotors.len()],
+            plugboard: self.plugboard,
+            wheels: state::fingerprint(wheels, Some(stepping)),
+            reflector: self.reflector.fingerprint(state::FINGERPRINT),
+        };
+        MachineState::new(&positions[..self.rotors.len()], self.reflector_position(), settings)
+    }
+
+    /// Returns to a checkpoint taken by `snapshot`, or read back with `MachineState::parse`.
+    ///
+    /// Like `set_window_positions`, this does not change the positions that `reset` returns to.
+    /// Fails, leaving the machine as it was, if the checkpoint was taken from a machine with other
+    /// settings: `MachineState::diff` shows which.
+    pub fn restore(&mut self, state: &MachineState) -> Result<(), &'static str> {
+        if !self.snapshot().same_settings(state) {
+            return Err("State was taken from a machine with other settings");
+        }
+        self.set_window_positions(state.window_positions());
+        self.reflector_position = usize::from(state.reflector_position());
+        Ok(())
+    }
+
+    /// Replugs the plugboard, leaving the rotors where they are.
+    pub fn set_plugboard(&mut self, plugboard: Plugboard) {
+        self.plugboard = plugboard;
+    }
+
+    /// Turns the rotors to new window positions, as an operator would by hand.
+    ///
+    /// This does not change the positions that `reset` returns to.
+    ///
+    /// Panics unless there is a position for each of the `rotor_count` rotors.
+    pub fn set_window_positions(&mut self, positions: &[Alpha]) {
+        assert_eq!(positions.len(),
+                   self.rotors.len(),
+                   "Must supply a window position for each rotor");
+        for (rr, &pos) in self.rotors.iter_mut().zip(positions) {
+            rr.window_position = RR::letter_to_position(rr.rotor, usize::from(pos));
+        }
+    }
+
+    #[cfg(feature = "alloc")]
+    pub fn get_window_positions(&self) -> Vec<Alpha> {
+        self.rotors
+            .iter()
+            .map(|rr| Alpha::try_from_usize(rr.window_letter()).unwrap())
+            .collect()
+    }
+
+    /// The letter showing in the window of the rotor in `slot`, counting from the left.
+    ///
+    /// Panics if there is no such rotor.
+    pub fn window_position(&self, slot: usize) -> Alpha {
+        Alpha::try_from_usize(self.rotors[slot].window_letter()).unwrap()
+    }
+
+    /// The position the reflector is turned to, on machines with a settable reflector.
+    pub fn reflector_position(&self) -> Alpha {
+        Alpha::try_from_usize(self.reflector_position).unwrap()
+    }
+
+    /// The number of rotors in the machine, including fixed wheels.
+    pub fn rotor_count(&self) -> usize {
+        self.rotors.len()
+    }
+
+    /// The rotor in `slot`, counting from the left.
+    ///
+    /// Panics if there is no such rotor.
+    pub fn rotor(&self, slot: usize) -> Rotor {
+        self.rotors[slot].rotor
+    }
+
+    /// Whether the rotor in `slot` steps, rather than being a fixed wheel or a stator.
+    ///
+    /// Panics if there is no such rotor.
+    pub fn rotor_steps(&self, slot: usize) -> bool {
+        self.rotors[slot].steps
+    }
+
+    pub fn reflector(&self) -> Reflector {
+        self.reflector
+    }
+
+    pub fn plugboard(&self) -> &Plugboard {
+        &self.plugboard
+    }
+
+    pub fn entry_wheel(&self) -> Option<EntryWheel> {
+        self.entry_wheel
+    }
+
+    pub fn stepping(&self) -> Stepping {
+        self.stepping
+    }
+
+    /// Presses a key, recording each hop the signal takes on its way to the lamp.
+    #[cfg(feature = "alloc")]
+    pub fn trace(&mut self, letter: Alpha) -> Trace {
+        let positions_before = self.get_window_positions();
+        self.step_and_turnover(&mut Unobserved);
+        let mut hops = Vec::new();
+        let lamp = self.encipher_with(CipherChar::from(letter), |component, offset, input, output| {
+            hops.push(Hop {
+                component,
+                offset: offset.map(|o| Alpha::try_from_usize(o).unwrap()),
+                input: Alpha::try_from_usize(input).unwrap(),
+                output: Alpha::try_from_usize(output).unwrap(),
+            })
+        });
+        Trace {
+            key: letter,
+            lamp: Alpha::try_from_usize(lamp).unwrap(),
+            positions_before,
+            positions_after: self.get_window_positions(),
+            hops,
+        }
+    }
+
+    /// Steps a copy of the machine from its current window positions until they repeat.
+    ///
+    /// This takes one step for every reachable window position, so it is only practical for
+    /// machines with a handful of stepping rotors.
+    #[cfg(feature = "alloc")]
+    pub fn cycle(&self) -> Cycle {
+        let mut machine = *self;
+        let mut seen = BTreeMap::new();
+        let mut positions = Vec::new();
+        loop {
+            let mut state: Vec<CipherChar> =
+                machine.rotors.iter().map(|rr| rr.window_position).collect();
+            state.push(machine.reflector_position);
+            if let Some(&transient) = seen.get(&state) {
+                return Cycle {
+                    transient,
+                    period: positions.len() - transient,
+                    positions,
+                };
+            }
+            seen.insert(state, positions.len());
+            positions.push(machine.get_window_positions());
+            machine.step_and_turnover(&mut Unobserved);
         }
-        positions
     }
 
     fn encipher(&self, letter: CipherChar) -> CipherChar {
-        let mut cipher_letter = letter;
-        for rr in self.rotors.iter().rev() {
+        self.encipher_with(letter, |_, _, _, _| ())
+    }
+
+    /// Enciphers a letter, calling `record` with the component, the rotor's contact offset (if
+    /// any), and the letter going in and out at each hop.
+    fn encipher_with<F>(&self, letter: CipherChar, mut record: F) -> CipherChar
+        where F: FnMut(Component, Option<CipherChar>, CipherChar, CipherChar)
+    {
+        let mut cipher_letter = self.plugboard.sub(letter);
+        record(Component::PlugboardIn, None, letter, cipher_letter);
+        if let Some(etw) = self.entry_wheel {
+            let cl = cipher_letter;
+            cipher_letter = etw.sub(cipher_letter);
+            record(Component::EntryWheelIn, None, cl, cipher_letter);
+        }
+        for (i, rr) in self.rotors.iter().enumerate().rev() {
             let cl = cipher_letter;
             let wp = rr.window_position;
             let sub = |l| rr.rotor.sub(l);
             cipher_letter = (sub((cl + wp) % 26) + 26 - wp) % 26;
+            record(Component::RotorForward(i), Some(rr.offset()), cl, cipher_letter);
         }
-        cipher_letter = self.reflector.sub(cipher_letter);
-        for rr in &self.rotors {
+        let cl = cipher_letter;
+        let rp = self.reflector_position;
+        cipher_letter = (self.reflector.sub((cl + rp) % 26) + 26 - rp) % 26;
+        record(Component::Reflector, None, cl, cipher_letter);
+        for (i, rr) in self.rotors.iter().enumerate() {
             let cl = cipher_letter;
             let wp = rr.window_position;
             let sub = |l| rr.rotor.inverse_sub(l);
             cipher_letter = (sub((cl + wp) % 26) + 26 - wp) % 26;
+            record(Component::RotorBackward(i), Some(rr.offset()), cl, cipher_letter);
         }
+        if let Some(etw) = self.entry_wheel {
+            let cl = cipher_letter;
+            cipher_letter = etw.inverse_sub(cipher_letter);
+            record(Component::EntryWheelOut, None, cl, cipher_letter);
+        }
+        let cl = cipher_letter;
+        cipher_letter = self.plugboard.sub(cipher_letter);
+        record(Component::PlugboardOut, None, cl, cipher_letter);
         cipher_letter
     }
 
-    fn step_and_turnover(&mut self) {
-        let mut to_step = [false, false, true];
-        for (i, rr) in self.rotors.iter().enumerate() {
-            let engaged = rr.rotor.notch_engaged(rr.window_position);
-            if engaged && i != 0 {
-                to_step[i] = true;
-                to_step[i - 1] = true;
+    fn step_and_turnover<O: Observer>(&mut self, observer: &mut O) {
+        match self.stepping {
+            Stepping::Ratchet => self.ratchet_step(observer),
+            Stepping::Gear => self.gear_step(observer),
+        }
+    }
+
+    fn ratchet_step<O: Observer>(&mut self, observer: &mut O) {
+        let mut to_step = [false; MAX_ROTORS];
+        let mut engaged = [false; MAX_ROTORS];
+        // The stepping rotor to the left of the one being looked at.
+        let mut left = None;
+        for (i, rr) in self.rotors.iter().enumerate().filter(|&(_, rr)| rr.steps) {
+            if let Some(left) = left {
+                if rr.rotor.notch_engaged(rr.window_position) {
+                    to_step[i] = true;
+                    to_step[left] = true;
+                    engaged[i] = true;
+                }
+            }
+            left = Some(i);
+        }
+        if let Some(right) = left {
+            to_step[right] = true;
+            for (slot, _) in engaged.iter().enumerate().filter(|&(_, &e)| e) {
+                observer.notify(Event::Turnover { slot });
+                // Any rotor but the right-most is pushed along by its own pawl.
+                if slot != right {
+                    observer.notify(Event::DoubleStep { slot });
+                }
             }
         }
         for (i, &should_step) in to_step.iter().enumerate() {
             if should_step {
-                self.step(i);
+                self.step(i, observer);
+            }
+        }
+    }
+
+    /// Each stepping rotor, starting from the right, carries the next one to its left round with
+    /// it as it passes a turnover. The left-most rotor carries the reflector.
+    fn gear_step<O: Observer>(&mut self, observer: &mut O) {
+        let mut carry = true;
+        for i in (0..self.rotors.len()).rev() {
+            let rr = self.rotors[i];
+            if !rr.steps {
+                continue;
             }
+            if !carry {
+                return;
+            }
+            carry = rr.rotor.notch_engaged(rr.window_position);
+            if carry {
+                observer.notify(Event::Turnover { slot: i });
+            }
+            self.step(i, observer);
+        }
+        if carry {
+            self.reflector_position = (self.reflector_position + 1) % 26;
+            observer.notify(Event::ReflectorStep { position: self.reflector_position() });
         }
     }
 
-    fn step(&mut self, rotor_index: usize) {
+    fn step<O: Observer>(&mut self, rotor_index: usize, observer: &mut O) {
         let curr_pos = self.rotors[rotor_index].window_position;
         self.rotors[rotor_index].window_position = (curr_pos + 1) % 26;
+        observer.notify(Event::Step {
+                            slot: rotor_index,
+                            window: self.window_position(rotor_index),
+                        });
     }
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, Default)]
 pub struct EnigmaBuilder {
     left_rotor: Option<Rotor>,
     mid_rotor: Option<Rotor>,
     right_rotor: Option<Rotor>,
 
-    window_positions: Option<[CipherChar; 3]>,
+    rotors: Option<Slots<Rotor>>,
+    fixed_rotors: Slots<Rotor>,
+    stators: Slots<Rotor>,
+
+    window_positions: Option<Slots<CipherChar>>,
 
     reflector: Option<Reflector>,
+
+    plugboard: Option<Plugboard>,
+
+    entry_wheel: Option<EntryWheel>,
+
+    stepping: Stepping,
+
+    reflector_position: CipherChar,
+
+    model: Option<MachineModel>,
+
+    error: Option<&'static str>,
 }
 
-impl Default for EnigmaBuilder {
-    fn default() -> Self {
-        EnigmaBuilder {
-            left_rotor: None,
-            mid_rotor: None,
-            right_rotor: None,
-            window_positions: None,
-            reflector: None,
+impl EnigmaBuilder {
+    /// A builder for one of the historical machines, with its entry wheel and stepping already
+    /// fitted. `build` fails if any part was not issued with the model, or if there are the wrong
+    /// number of rotors.
+    pub fn for_model(model: MachineModel) -> EnigmaBuilder {
+        let builder = EnigmaBuilder {
+            stepping: model.stepping(),
+            model: Some(model),
+            ..EnigmaBuilder::default()
+        };
+        match model.entry_wheel() {
+            Some(etw) => builder.entry_wheel(etw),
+            None => builder,
         }
     }
-}
 
-impl EnigmaBuilder {
     pub fn left_rotor(mut self, rotor: Rotor) -> EnigmaBuilder {
         self.left_rotor = Some(rotor);
         self
@@ -127,60 +582,183 @@ impl EnigmaBuilder {
         self
     }
 
+    /// The stepping rotors, from left to right.
+    ///
+    /// This is an alternative to `left_rotor`, `mid_rotor` and `right_rotor` for machines with a
+    /// different number of rotors.
+    pub fn rotors<R: AsRef<[Rotor]>>(mut self, rotors: R) -> EnigmaBuilder {
+        self.rotors = self.slots(rotors.as_ref());
+        self
+    }
+
+    /// Non-stepping wheels which sit to the left of the stepping rotors, from left to right.
+    pub fn fixed_rotors<R: AsRef<[Rotor]>>(mut self, rotors: R) -> EnigmaBuilder {
+        self.fixed_rotors = self.slots(rotors.as_ref()).unwrap_or_default();
+        self
+    }
+
+    /// Non-stepping wheels which sit to the right of the stepping rotors, between them and the
+    /// plugboard, from left to right.
+    pub fn stators<R: AsRef<[Rotor]>>(mut self, rotors: R) -> EnigmaBuilder {
+        self.stators = self.slots(rotors.as_ref()).unwrap_or_default();
+        self
+    }
+
     pub fn reflector(mut self, reflector: Reflector) -> EnigmaBuilder {
         self.reflector = Some(reflector);
         self
     }
 
-    pub fn window_positions(mut self, positions: [Alpha; 3]) -> EnigmaBuilder {
-        let mut window_positions: [CipherChar; 3] = [0; 3];
-        for (i, &v) in positions.iter().enumerate() {
-            window_positions[i] = usize::from(v);
+    pub fn plugboard(mut self, plugboard: Plugboard) -> EnigmaBuilder {
+        self.plugboard = Some(plugboard);
+        self
+    }
+
+    /// The entry wheel, for machines which do not wire the keys to the rotors alphabetically.
+    pub fn entry_wheel(mut self, entry_wheel: EntryWheel) -> EnigmaBuilder {
+        self.entry_wheel = Some(entry_wheel);
+        self
+    }
+
+    /// How the rotors step. The default is `Stepping::Ratchet`.
+    pub fn stepping(mut self, stepping: Stepping) -> EnigmaBuilder {
+        self.stepping = stepping;
+        self
+    }
+
+    /// The starting position of a settable reflector. The default is `A`.
+    pub fn reflector_position(mut self, position: Alpha) -> EnigmaBuilder {
+        self.reflector_position = usize::from(position);
+        self
+    }
+
+    /// The window positions of all rotors, including fixed wheels, from left to right.
+    pub fn window_positions<P: AsRef<[Alpha]>>(mut self, positions: P) -> EnigmaBuilder {
+        let mut window_positions = Slots::default();
+        for &p in positions.as_ref() {
+            if let Err(e) = window_positions.push(usize::from(p)) {
+                self.error = Some(e);
+            }
         }
         self.window_positions = Some(window_positions);
         self
     }
 
+    /// Copies a list of rotors, remembering the error for `build` if there are too many.
+    fn slots(&mut self, rotors: &[Rotor]) -> Option<Slots<Rotor>> {
+        match Slots::from_slice(rotors) {
+            Ok(slots) => Some(slots),
+            Err(e) => {
+                self.error = Some(e);
+                None
+            }
+        }
+    }
+
+    /// Builds the machine, failing if any part is missing or does not fit, e.g. if there are more
+    /// than `MAX_ROTORS` rotors.
     pub fn build(self) -> Result<Enigma, &'static str> {
+        if let Some(e) = self.error {
+            return Err(e);
+        }
         let window_positions = match self.window_positions {
             Some(r) => r,
             None => return Err("Must supply initial rotor positions"),
         };
-        let left_rotor = match self.left_rotor {
-            Some(r) => {
-                RR {
-                    rotor: r,
-                    window_position: window_positions[0],
+        let stepping_rotors = match self.rotors {
+            Some(rotors) => {
+                if self.left_rotor.is_some() || self.mid_rotor.is_some() ||
+                   self.right_rotor.is_some() {
+                    return Err("Cannot supply both a list of rotors and individual rotors");
                 }
-            }
-            None => return Err("Must supply left rotor"),
-        };
-        let mid_rotor = match self.mid_rotor {
-            Some(r) => {
-                RR {
-                    rotor: r,
-                    window_position: window_positions[1],
+                if rotors.is_empty() {
+                    return Err("Must supply at least one stepping rotor");
                 }
+                rotors
             }
-            None => return Err("Must supply mid rotor"),
-        };
-        let right_rotor = match self.right_rotor {
-            Some(r) => {
-                RR {
-                    rotor: r,
-                    window_position: window_positions[2],
-                }
+            None => {
+                let left_rotor = match self.left_rotor {
+                    Some(r) => r,
+                    None => return Err("Must supply left rotor"),
+                };
+                let mid_rotor = match self.mid_rotor {
+                    Some(r) => r,
+                    None => return Err("Must supply mid rotor"),
+                };
+                let right_rotor = match self.right_rotor {
+                    Some(r) => r,
+                    None => return Err("Must supply right rotor"),
+                };
+                Slots::from_slice(&[left_rotor, mid_rotor, right_rotor])?
             }
-            None => return Err("Must supply right rotor"),
         };
         let reflector = match self.reflector {
             Some(r) => r,
             None => return Err("Must supply reflector"),
         };
+        if let Some(model) = self.model {
+            self.check_model(model, &stepping_rotors, reflector)?;
+        }
+
+        let fixed = self.fixed_rotors.iter().map(|&r| (r, false));
+        let stepping = stepping_rotors.iter().map(|&r| (r, true));
+        let stators = self.stators.iter().map(|&r| (r, false));
+        let mut rotors = Slots::default();
+        let mut all_rotors = fixed.chain(stepping).chain(stators);
+        for &window_letter in window_positions.iter() {
+            match all_rotors.next() {
+                Some((rotor, steps)) => rotors.push(RR::new(rotor, window_letter, steps))?,
+                None => return Err("Must supply a window position for each rotor"),
+            }
+        }
+        if all_rotors.next().is_some() {
+            return Err("Must supply a window position for each rotor");
+        }
+
         Ok(Enigma {
-            rotors: [left_rotor, mid_rotor, right_rotor],
-            reflector: reflector,
+            rotors,
+            reflector,
+            plugboard: self.plugboard.unwrap_or_default(),
+            entry_wheel: self.entry_wheel,
+            stepping: self.stepping,
+            reflector_position: self.reflector_position,
             original_window_positions: window_positions,
+            original_reflector_position: self.reflector_position,
         })
     }
+
+    fn check_model(&self,
+                   model: MachineModel,
+                   stepping_rotors: &Slots<Rotor>,
+                   reflector: Reflector)
+                   -> Result<(), &'static str> {
+        let fixed_slots = model.fixed_slots();
+        if stepping_rotors.len() != model.slots() - fixed_slots ||
+           self.fixed_rotors.len() != fixed_slots || !self.stators.is_empty() {
+            return Err("Wrong number of rotors for this model");
+        }
+        let issued = |rotor: &Rotor, wheels: &[WheelSpec]| {
+            wheels.iter().any(|w| rotor.is_from(&w.factory()))
+        };
+        if !stepping_rotors.iter().all(|r| issued(r, model.rotors())) ||
+           !self.fixed_rotors.iter().all(|r| issued(r, model.fixed_rotors())) {
+            return Err("Rotor was not issued with this model");
+        }
+        if !model.reflectors().iter().any(|r| r.reflector() == reflector) {
+            return Err("Reflector was not issued with this model");
+        }
+        if self.entry_wheel != model.entry_wheel() {
+            return Err("Entry wheel does not belong to this model");
+        }
+        if !model.has_plugboard() && self.plugboard.is_some_and(|p| p != Plugboard::default()) {
+            return Err("This model has no plugboard");
+        }
+        if !model.settable_reflector() && self.reflector_position != 0 {
+            return Err("This model's reflector cannot be set");
+        }
+        if self.stepping != model.stepping() {
+            return Err("This model does not step that way");
+        }
+        Ok(())
+    }
 }
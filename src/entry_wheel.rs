@@ -0,0 +1,60 @@
+use cipher;
+use cipher::Cipher;
+use cipher::CipherChar;
+use state;
+
+/// The Entry Wheel ('Eintrittswalze') connects the keyboard to the rotors.
+///
+/// On military machines the keys are wired to the contacts in alphabetical order, so it has no
+/// effect. Commercial machines such as the Enigma K wire them in keyboard order instead.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct EntryWheel {
+    cipher: Cipher,
+    inverse: Cipher,
+}
+
+impl EntryWheel {
+    /// Creates an entry wheel from the keys wired to each contact, in contact order. For
+    /// example, `"QWERTZUIOASDFGHJKPYXCVBNML"` wires `Q` to the first contact.
+    ///
+    /// Panics if the wiring is not a permutation of the alphabet.
+    pub fn from_wiring(wiring: &str) -> EntryWheel {
+        let inverse = cipher::from_string(wiring);
+        let mut cipher = [0; 26];
+        for (contact, &key) in inverse.iter().enumerate() {
+            cipher[key] = contact;
+        }
+        EntryWheel { cipher, inverse }
+    }
+
+    /// The contact reached from a key.
+    pub fn sub(&self, c: CipherChar) -> CipherChar {
+        self.cipher[c]
+    }
+
+    /// The lamp reached from a contact.
+    pub fn inverse_sub(&self, c: CipherChar) -> CipherChar {
+        self.inverse[c]
+    }
+
+    /// Folds the wiring into `hash`.
+    pub(crate) fn fingerprint(&self, hash: u64) -> u64 {
+        state::fingerprint(hash, self.cipher.iter().cloned())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cipher::from_char as CC;
+
+    #[test]
+    fn keyboard_order() {
+        let etw = EntryWheel::from_wiring("QWERTZUIOASDFGHJKPYXCVBNML");
+
+        assert_eq!(CC('A'), etw.sub(CC('Q')));
+        assert_eq!(CC('B'), etw.sub(CC('W')));
+        assert_eq!(CC('Q'), etw.inverse_sub(CC('A')));
+        assert_eq!(CC('L'), etw.inverse_sub(CC('Z')));
+    }
+}
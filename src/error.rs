@@ -0,0 +1,71 @@
+use core::fmt;
+use core::ops::Range;
+
+use alpha::InvalidCharacter;
+
+/// Everything that can go wrong when setting up or using a machine from outside Rust.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Error {
+    /// A key string could not be understood.
+    Key(KeyError),
+    /// The parts given do not make a working machine, or a machine cannot be written as a key.
+    Machine(&'static str),
+    /// The plugboard pairs are not valid.
+    Plugboard(&'static str),
+    InvalidCharacter(InvalidCharacter),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Error::Key(e) => write!(f, "Invalid key: {}", e),
+            Error::Machine(e) => write!(f, "Invalid machine: {}", e),
+            Error::Plugboard(e) => write!(f, "Invalid plugboard: {}", e),
+            Error::InvalidCharacter(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl ::std::error::Error for Error {}
+
+/// What was wrong with a key string, and where.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct KeyError {
+    message: &'static str,
+    start: usize,
+    end: usize,
+}
+
+impl KeyError {
+    pub fn new(message: &'static str, span: Range<usize>) -> KeyError {
+        KeyError { message, start: span.start, end: span.end }
+    }
+
+    pub fn message(&self) -> &'static str {
+        self.message
+    }
+
+    /// The bytes of the key string holding the offending token. This is empty when something is
+    /// missing, and then points at where it should have been.
+    pub fn span(&self) -> Range<usize> {
+        self.start..self.end
+    }
+
+    /// The offending token, taken from the key string which was parsed.
+    pub fn token<'a>(&self, key: &'a str) -> &'a str {
+        &key[self.span()]
+    }
+}
+
+impl fmt::Display for KeyError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} at {}..{}", self.message, self.start, self.end)
+    }
+}
+
+impl From<InvalidCharacter> for Error {
+    fn from(e: InvalidCharacter) -> Error {
+        Error::InvalidCharacter(e)
+    }
+}
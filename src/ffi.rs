@@ -0,0 +1,227 @@
+//! A C interface, enabled with the `ffi` feature.
+//!
+//! The header is `include/enigma.h`, generated with `cbindgen --config cbindgen.toml --output
+//! include/enigma.h`. Every function returns an `EnigmaStatus`, which is zero on success.
+//!
+//! Machines are created from key strings, as parsed by `key::parse`, and are freed with
+//! `enigma_free`.
+
+// Raw pointers from C are unavoidable here.
+#![allow(unsafe_code)]
+
+use alloc::boxed::Box;
+use std::ffi::CStr;
+use std::os::raw::c_char;
+use std::os::raw::c_int;
+use std::ptr;
+
+use alpha::Alpha;
+use enigma::Enigma;
+use error::Error;
+use key;
+
+/// The result of a call.
+#[repr(C)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum EnigmaStatus {
+    Ok = 0,
+    NullPointer = 1,
+    InvalidUtf8 = 2,
+    InvalidKey = 3,
+    InvalidMachine = 4,
+    InvalidPlugboard = 5,
+    InvalidCharacter = 6,
+    BufferTooSmall = 7,
+}
+
+const STATUSES: [EnigmaStatus; 8] = [EnigmaStatus::Ok,
+                                     EnigmaStatus::NullPointer,
+                                     EnigmaStatus::InvalidUtf8,
+                                     EnigmaStatus::InvalidKey,
+                                     EnigmaStatus::InvalidMachine,
+                                     EnigmaStatus::InvalidPlugboard,
+                                     EnigmaStatus::InvalidCharacter,
+                                     EnigmaStatus::BufferTooSmall];
+
+impl EnigmaStatus {
+    /// The status with the value `status`, if there is one.
+    fn from_raw(status: c_int) -> Option<EnigmaStatus> {
+        STATUSES.iter().cloned().find(|&s| s as c_int == status)
+    }
+}
+
+impl From<Error> for EnigmaStatus {
+    fn from(e: Error) -> EnigmaStatus {
+        match e {
+            Error::Key(_) => EnigmaStatus::InvalidKey,
+            Error::Machine(_) => EnigmaStatus::InvalidMachine,
+            Error::Plugboard(_) => EnigmaStatus::InvalidPlugboard,
+            Error::InvalidCharacter(_) => EnigmaStatus::InvalidCharacter,
+        }
+    }
+}
+
+/// A machine, owned by the caller until it is passed to `enigma_free`.
+#[derive(Debug, Copy, Clone)]
+pub struct EnigmaHandle {
+    enigma: Enigma,
+}
+
+/// Creates a machine from a key string, e.g. `"B II IV V / 02 21 12 / AV BS CG / BLA"`.
+///
+/// On success, `*handle` is set to the new machine.
+///
+/// # Safety
+///
+/// `key` must be a NUL-terminated string, and `handle` must be valid to write to.
+#[no_mangle]
+pub unsafe extern "C" fn enigma_new(key: *const c_char,
+                                    handle: *mut *mut EnigmaHandle)
+                                    -> EnigmaStatus {
+    if key.is_null() || handle.is_null() {
+        return EnigmaStatus::NullPointer;
+    }
+    let key = match CStr::from_ptr(key).to_str() {
+        Ok(key) => key,
+        Err(_) => return EnigmaStatus::InvalidUtf8,
+    };
+    let enigma = match key::parse(key).and_then(|b| b.build().map_err(Error::Machine)) {
+        Ok(enigma) => enigma,
+        Err(e) => return e.into(),
+    };
+    *handle = Box::into_raw(Box::new(EnigmaHandle { enigma }));
+    EnigmaStatus::Ok
+}
+
+/// Frees a machine created by `enigma_new`. Does nothing if `handle` is null.
+///
+/// # Safety
+///
+/// `handle` must have come from `enigma_new`, and must not be used afterwards.
+#[no_mangle]
+pub unsafe extern "C" fn enigma_free(handle: *mut EnigmaHandle) {
+    if !handle.is_null() {
+        drop(Box::from_raw(handle));
+    }
+}
+
+/// Presses a key, an ASCII letter in either case, and sets `*lamp` to the upper case letter which
+/// lights up.
+///
+/// # Safety
+///
+/// `handle` must have come from `enigma_new`, and `lamp` must be valid to write to.
+#[no_mangle]
+pub unsafe extern "C" fn enigma_press(handle: *mut EnigmaHandle,
+                                      key: c_char,
+                                      lamp: *mut c_char)
+                                      -> EnigmaStatus {
+    if handle.is_null() || lamp.is_null() {
+        return EnigmaStatus::NullPointer;
+    }
+    match letter(key) {
+        Some(l) => {
+            *lamp = ascii((*handle).enigma.press(l));
+            EnigmaStatus::Ok
+        }
+        None => EnigmaStatus::InvalidCharacter,
+    }
+}
+
+/// Enciphers `len` bytes of `input` into `output`. Letters come out in upper case, and other
+/// bytes are copied without pressing a key.
+///
+/// `input` and `output` may be the same buffer.
+///
+/// # Safety
+///
+/// `handle` must have come from `enigma_new`, and both buffers must hold at least `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn enigma_encipher(handle: *mut EnigmaHandle,
+                                         input: *const c_char,
+                                         output: *mut c_char,
+                                         len: usize)
+                                         -> EnigmaStatus {
+    if handle.is_null() || input.is_null() || output.is_null() {
+        return EnigmaStatus::NullPointer;
+    }
+    let enigma = &mut (*handle).enigma;
+    for i in 0..len {
+        let c = ptr::read(input.add(i));
+        let out = match letter(c) {
+            Some(l) => ascii(enigma.press(l)),
+            None => c,
+        };
+        ptr::write(output.add(i), out);
+    }
+    EnigmaStatus::Ok
+}
+
+/// Writes the letters showing in the windows, from left to right, as a NUL-terminated string.
+///
+/// `len` is the size of `positions`, which needs a byte for each rotor and one for the NUL.
+///
+/// # Safety
+///
+/// `handle` must have come from `enigma_new`, and `positions` must hold at least `len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn enigma_get_positions(handle: *const EnigmaHandle,
+                                              positions: *mut c_char,
+                                              len: usize)
+                                              -> EnigmaStatus {
+    if handle.is_null() || positions.is_null() {
+        return EnigmaStatus::NullPointer;
+    }
+    let enigma = &(*handle).enigma;
+    let count = enigma.rotor_count();
+    if len < count + 1 {
+        return EnigmaStatus::BufferTooSmall;
+    }
+    for i in 0..count {
+        ptr::write(positions.add(i), ascii(enigma.window_position(i)));
+    }
+    ptr::write(positions.add(count), 0);
+    EnigmaStatus::Ok
+}
+
+/// Returns the rotors to the window positions in the key.
+///
+/// # Safety
+///
+/// `handle` must have come from `enigma_new`.
+#[no_mangle]
+pub unsafe extern "C" fn enigma_reset(handle: *mut EnigmaHandle) -> EnigmaStatus {
+    if handle.is_null() {
+        return EnigmaStatus::NullPointer;
+    }
+    (*handle).enigma.reset();
+    EnigmaStatus::Ok
+}
+
+/// A description of a status, as a static NUL-terminated string.
+///
+/// The status is taken as a plain `int`, since C may pass any value: one which is not an
+/// `EnigmaStatus` gives "unknown status".
+#[no_mangle]
+pub extern "C" fn enigma_status_message(status: c_int) -> *const c_char {
+    let message: &'static [u8] = match EnigmaStatus::from_raw(status) {
+        Some(EnigmaStatus::Ok) => b"ok\0",
+        Some(EnigmaStatus::NullPointer) => b"null pointer\0",
+        Some(EnigmaStatus::InvalidUtf8) => b"key is not valid UTF-8\0",
+        Some(EnigmaStatus::InvalidKey) => b"invalid key\0",
+        Some(EnigmaStatus::InvalidMachine) => b"invalid machine\0",
+        Some(EnigmaStatus::InvalidPlugboard) => b"invalid plugboard\0",
+        Some(EnigmaStatus::InvalidCharacter) => b"not a letter\0",
+        Some(EnigmaStatus::BufferTooSmall) => b"buffer too small\0",
+        None => b"unknown status\0",
+    };
+    message.as_ptr() as *const c_char
+}
+
+fn letter(c: c_char) -> Option<Alpha> {
+    Alpha::try_from_char(char::from(c as u8)).ok()
+}
+
+fn ascii(l: Alpha) -> c_char {
+    char::from(l) as u8 as c_char
+}
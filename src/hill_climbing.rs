@@ -0,0 +1,267 @@
+//! Recovering the plugboard by local search, once the rest of the key is known.
+//!
+//! The search starts from a plugboard (usually an empty one) and repeatedly tries a small change:
+//!
+//! - `Move::Add` plugs two free letters together
+//! - `Move::Remove` unplugs a pair
+//! - `Move::Swap` moves one end of a pair to another letter, which swaps partners with that
+//!   letter's pair if it has one
+//!
+//! A change is kept if it improves the score. With simulated annealing, a change for the worse is
+//! also kept with probability `exp(difference / temperature)`, where the temperature falls after
+//! every move. This lets the search climb out of local maxima early on, and settle later. The
+//! whole search is repeated from the start for each restart, and the best plugboard seen is kept.
+
+use alloc::vec::Vec;
+
+use alpha::Alpha;
+use enigma::Enigma;
+use plugboard::Plugboard;
+use random::Rng;
+use scoring::NgramModel;
+
+/// A change to try to the plugboard.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Move {
+    Swap,
+    Add,
+    Remove,
+}
+
+/// How to search.
+#[derive(Debug, Clone)]
+pub struct HillClimb {
+    /// The number of times to search from the initial plugboard.
+    pub restarts: usize,
+    /// The number of moves to try on each restart.
+    pub iterations: usize,
+    /// The moves to choose from, at random.
+    pub moves: Vec<Move>,
+    /// The most pairs to plug, usually 10.
+    pub max_pairs: usize,
+    /// The starting temperature for simulated annealing. Zero gives plain hill climbing, where
+    /// only improvements are kept.
+    pub temperature: f64,
+    /// The fraction of the temperature left after each move.
+    pub cooling: f64,
+    /// The seed for the random choices, so that a search can be repeated exactly.
+    pub seed: u64,
+}
+
+/// The result of a search.
+#[derive(Debug, Clone)]
+pub struct Outcome {
+    /// The best plugboard found.
+    pub plugboard: Plugboard,
+    pub pairs: Vec<(Alpha, Alpha)>,
+    pub score: f64,
+    /// The score after each move, one list per restart, starting with the initial score.
+    pub trajectories: Vec<Vec<f64>>,
+}
+
+impl Default for HillClimb {
+    fn default() -> Self {
+        HillClimb {
+            restarts: 5,
+            iterations: 2000,
+            moves: vec![Move::Swap, Move::Add, Move::Remove],
+            max_pairs: 10,
+            temperature: 1.0,
+            cooling: 0.998,
+            seed: 0,
+        }
+    }
+}
+
+impl HillClimb {
+    /// Searches for the plugboard with the highest `score`, starting from `initial`.
+    ///
+    /// Panics if there are no moves to choose from.
+    pub fn run<F>(&self, initial: &Plugboard, mut score: F) -> Outcome
+        where F: FnMut(&Plugboard) -> f64
+    {
+        assert!(!self.moves.is_empty(), "Must allow at least one move");
+        let mut rng = Rng::new(self.seed);
+        let initial_score = score(initial);
+        let mut best = Outcome {
+            plugboard: *initial,
+            pairs: initial.pairs(),
+            score: initial_score,
+            trajectories: Vec::new(),
+        };
+        for _ in 0..self.restarts {
+            let mut pairs = initial.pairs();
+            let mut current = initial_score;
+            let mut temperature = self.temperature;
+            let mut trajectory = vec![current];
+            for _ in 0..self.iterations {
+                let mv = self.moves[rng.below(self.moves.len())];
+                if let Some(candidate) = neighbour(&pairs, mv, self.max_pairs, &mut rng) {
+                    let plugboard = Plugboard::new(&candidate).unwrap();
+                    let s = score(&plugboard);
+                    let difference = s - current;
+                    if difference >= 0.0 ||
+                       temperature > 0.0 && rng.chance((difference / temperature).exp()) {
+                        pairs = candidate;
+                        current = s;
+                        if current > best.score {
+                            best.plugboard = plugboard;
+                            best.pairs = plugboard.pairs();
+                            best.score = current;
+                        }
+                    }
+                }
+                temperature *= self.cooling;
+                trajectory.push(current);
+            }
+            best.trajectories.push(trajectory);
+        }
+        best
+    }
+}
+
+/// A scoring function for `HillClimb::run`: the n-gram score of `ciphertext` deciphered by
+/// `enigma`, from its current positions, with each plugboard tried.
+pub fn deciphering<'a>(enigma: Enigma,
+                       ciphertext: &'a [Alpha],
+                       model: &'a NgramModel)
+                       -> impl FnMut(&Plugboard) -> f64 + 'a {
+    let mut plaintext = vec![Alpha::A; ciphertext.len()];
+    move |plugboard| {
+        let mut machine = enigma;
+        machine.set_plugboard(*plugboard);
+        machine.encipher_into(ciphertext, &mut plaintext);
+        model.score(&plaintext)
+    }
+}
+
+/// The pairs after making `mv`, or `None` if it cannot be made.
+fn neighbour(pairs: &[(Alpha, Alpha)], mv: Move, max_pairs: usize, rng: &mut Rng)
+             -> Option<Vec<(Alpha, Alpha)>> {
+    let mut pairs = pairs.to_vec();
+    let partner = |pairs: &[(Alpha, Alpha)], l: Alpha| {
+        pairs.iter().position(|&(a, b)| a == l || b == l)
+    };
+    match mv {
+        Move::Add => {
+            let free: Vec<Alpha> =
+                Alpha::all().filter(|&l| partner(&pairs, l).is_none()).collect();
+            if pairs.len() >= max_pairs || free.len() < 2 {
+                return None;
+            }
+            let a = free[rng.below(free.len())];
+            let others: Vec<Alpha> = free.into_iter().filter(|&l| l != a).collect();
+            pairs.push((a, others[rng.below(others.len())]));
+        }
+        Move::Remove => {
+            if pairs.is_empty() {
+                return None;
+            }
+            let i = rng.below(pairs.len());
+            pairs.swap_remove(i);
+        }
+        Move::Swap => {
+            if pairs.is_empty() {
+                return None;
+            }
+            let i = rng.below(pairs.len());
+            let (a, b) = if rng.chance(0.5) { pairs[i] } else { (pairs[i].1, pairs[i].0) };
+            let others: Vec<Alpha> = Alpha::all().filter(|&l| l != a && l != b).collect();
+            let c = others[rng.below(others.len())];
+            match partner(&pairs, c) {
+                Some(j) => {
+                    let d = if pairs[j].0 == c { pairs[j].1 } else { pairs[j].0 };
+                    pairs[i] = (a, c);
+                    pairs[j] = (b, d);
+                }
+                None => pairs[i] = (a, c),
+            }
+        }
+    }
+    Some(pairs)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alpha::Alpha::*;
+    use enigma::EnigmaBuilder;
+    use reflector::Reflectors;
+    use rotor::Rotors;
+    use scoring::coincidences;
+
+    fn machine() -> Enigma {
+        let rotors = Rotors::default();
+        EnigmaBuilder::default()
+            .rotors([rotors.ii.with_ring_setting(B), rotors.iv.with_ring_setting(U),
+                     rotors.v.with_ring_setting(L)])
+            .reflector(Reflectors::default().b)
+            .window_positions([B, L, A])
+            .build()
+            .unwrap()
+    }
+
+    #[test]
+    fn moves_keep_the_plugboard_valid() {
+        let mut rng = Rng::new(1);
+        let mut pairs = Vec::new();
+        for i in 0..1000 {
+            let mv = [Move::Swap, Move::Add, Move::Remove][i % 3];
+            if let Some(next) = neighbour(&pairs, mv, 10, &mut rng) {
+                assert!(Plugboard::new(&next).is_ok());
+                assert!(next.len() <= 10);
+                pairs = next;
+            }
+        }
+        assert!(neighbour(&[], Move::Remove, 10, &mut rng).is_none());
+        assert!(neighbour(&[], Move::Swap, 10, &mut rng).is_none());
+        assert!(neighbour(&[(A, B)], Move::Add, 1, &mut rng).is_none());
+    }
+
+    #[test]
+    fn recovers_plugboard_from_known_plaintext() {
+        let plaintext = Alpha::from_string("KEINEBESONDERENEREIGNISSEXEIGENELAGEUNVERAENDERTXFEIND\
+                                            RUHIGXNACHSCHUBEINGETROFFEN");
+        let plugboard = Plugboard::new(&[(A, V), (B, S), (C, G), (D, L), (F, U), (H, Z)]).unwrap();
+        let mut enigma = machine();
+        enigma.set_plugboard(plugboard);
+        let ciphertext = enigma.message(&plaintext);
+
+        let search = HillClimb {
+            restarts: 3,
+            iterations: 1500,
+            ..HillClimb::default()
+        };
+        let outcome = search.run(&Plugboard::default(), |p| {
+            let mut machine = machine();
+            machine.set_plugboard(*p);
+            coincidences(&machine.message(&ciphertext), &plaintext) as f64
+        });
+        assert_eq!(plugboard, outcome.plugboard);
+        assert_eq!(plaintext.len() as f64, outcome.score);
+        assert_eq!(3, outcome.trajectories.len());
+        assert!(outcome.trajectories.iter().all(|t| t.len() == 1501));
+
+        // The same seed gives the same search.
+        let again = search.run(&Plugboard::default(), |p| {
+            let mut machine = machine();
+            machine.set_plugboard(*p);
+            coincidences(&machine.message(&ciphertext), &plaintext) as f64
+        });
+        assert_eq!(outcome.trajectories, again.trajectories);
+    }
+
+    #[test]
+    fn scores_deciphered_text() {
+        let plaintext = Alpha::from_string("DIEDIVISIONWIRDMORGENFRUEHUMSECHSUHRANGREIFEN");
+        let plugboard = Plugboard::new(&[(A, V), (B, S)]).unwrap();
+        let mut enigma = machine();
+        enigma.set_plugboard(plugboard);
+        let ciphertext = enigma.message(&plaintext);
+
+        let model = NgramModel::german();
+        let mut score = deciphering(machine(), &ciphertext, &model);
+        assert_eq!(model.score(&plaintext), score(&plugboard));
+        assert!(score(&plugboard) > score(&Plugboard::default()));
+    }
+}
@@ -0,0 +1,409 @@
+//! Key strings: a day's settings written out on one line, as on a key sheet.
+//!
+//! For example `B II IV V / 02 21 12 / AV BS CG DL FU HZ IN KM OW RX / BLA` has four parts,
+//! separated by slashes:
+//!
+//! - the reflector, then the rotors from left to right, by their names in the catalogue of the
+//!   Enigma I, M3 and M4: `I` to `VIII`, the Greek wheels `Beta` and `Gamma`, and the reflectors
+//!   `A`, `B`, `C`, `B thin` and `C thin`. As on the M4, a thin reflector goes with a single Greek
+//!   wheel, which comes first and does not step.
+//! - the ring settings, as numbers from `01` to `26` or as letters
+//! - the plugboard pairs, which may be empty
+//! - the window positions
+//!
+//! `parse` turns a key string into an `EnigmaBuilder`, and `format` writes one out for a machine.
+
+#[cfg(feature = "alloc")]
+use alloc::string::String;
+#[cfg(feature = "alloc")]
+use core::fmt::Write;
+
+use alpha::Alpha;
+#[cfg(feature = "alloc")]
+use enigma::Enigma;
+use enigma::EnigmaBuilder;
+use enigma::MAX_ROTORS;
+use error::Error;
+use error::KeyError;
+use model::MachineModel;
+use model::ReflectorSpec;
+#[cfg(feature = "alloc")]
+use model::Stepping;
+use model::WheelSpec;
+use plugboard::Plugboard;
+use rotor::RotorFactory;
+use tokens;
+use tokens::Token;
+
+/// The machines whose parts a key string can name.
+const MODELS: [MachineModel; 3] = [MachineModel::EnigmaI, MachineModel::M3, MachineModel::M4];
+
+/// Parses a key string into a builder for the machine it describes.
+///
+/// A key string which cannot be understood gives an `Error::Key`, whose span points at the
+/// offending token in `key`.
+pub fn parse(key: &str) -> Result<EnigmaBuilder, Error> {
+    let parser = Parser { key };
+    let mut parts = key.split('/');
+    let (wheels, rings, plugs, windows) =
+        match (parts.next(), parts.next(), parts.next(), parts.next(), parts.next()) {
+            (Some(w), Some(r), Some(p), Some(s), None) => (w, r, p, s),
+            (_, _, _, _, Some(extra)) => {
+                return Err(parser.error("Must have four parts separated by '/'", extra.trim()))
+            }
+            _ => return Err(parser.missing("Must have four parts separated by '/'", key)),
+        };
+
+    let mut names = wheels.split_whitespace().peekable();
+    let first = names.next().ok_or(parser.missing("Must supply a reflector", wheels))?;
+    // Thin reflectors are named in two words, e.g. `B thin`.
+    let reflector = match names.peek() {
+        Some(&thin) if thin == "thin" => {
+            names.next();
+            let start = first.as_ptr() as usize - wheels.as_ptr() as usize;
+            let end = thin.as_ptr() as usize - wheels.as_ptr() as usize + thin.len();
+            &wheels[start..end]
+        }
+        _ => first,
+    };
+    let words = |name: &'static str| name.split_whitespace().eq(reflector.split_whitespace());
+    let reflector_token = reflector;
+    let reflector = reflector_spec(|spec| words(spec.name))
+        .ok_or(parser.error("Unknown reflector", reflector))?;
+
+    let mut factories: [Option<RotorFactory>; MAX_ROTORS] = [None; MAX_ROTORS];
+    let mut greek = None;
+    let mut fixed = 0;
+    let mut count = 0;
+    for name in names {
+        let factory = match (rotor_spec(name, false), rotor_spec(name, true)) {
+            (Some(spec), _) => spec.factory(),
+            (None, Some(_)) if greek.is_some() => {
+                return Err(parser.error("Only one Greek wheel can be fitted", name))
+            }
+            (None, Some(spec)) if count == 0 => {
+                greek = Some(name);
+                fixed += 1;
+                spec.factory()
+            }
+            (None, Some(_)) => return Err(parser.error("Greek wheels must come first", name)),
+            (None, None) => return Err(parser.error("Unknown rotor", name)),
+        };
+        let slot = factories.get_mut(count);
+        *slot.ok_or(parser.error("Too many rotors: at most MAX_ROTORS (8)", name))? = Some(factory);
+        count += 1;
+    }
+    if count == fixed {
+        return Err(parser.missing("Must supply at least one rotor", wheels));
+    }
+    match (is_thin(reflector), greek) {
+        (true, None) => {
+            return Err(parser.error("A thin reflector needs a Greek wheel", reflector_token))
+        }
+        (false, Some(greek)) => {
+            return Err(parser.error("A Greek wheel needs a thin reflector", greek))
+        }
+        _ => {}
+    }
+    let first = factories[0].unwrap();
+
+    let rings = parser.exactly(tokens::ring_settings(rings),
+                               rings,
+                               count,
+                               "Must supply a ring setting for each rotor")?;
+    let mut rotors = [first.with_ring_setting(Alpha::A); MAX_ROTORS];
+    for i in 0..count {
+        rotors[i] = factories[i].unwrap().with_ring_setting(rings[i]);
+    }
+
+    let mut pairs = [(Alpha::A, Alpha::A); 13];
+    let mut plug_count = 0;
+    for (token, pair) in tokens::pairs(plugs) {
+        let pair = pair.map_err(|message| parser.error(message, token))?;
+        *pairs.get_mut(plug_count).ok_or(parser.error("Too many plugboard pairs", token))? = pair;
+        plug_count += 1;
+    }
+    let plugboard = Plugboard::new(&pairs[..plug_count]).map_err(Error::Plugboard)?;
+
+    let windows = parser.exactly(tokens::letters(windows),
+                                 windows,
+                                 count,
+                                 "Must supply a window position for each rotor")?;
+
+    Ok(EnigmaBuilder::default()
+           .fixed_rotors(&rotors[..fixed])
+           .rotors(&rotors[fixed..count])
+           .reflector(reflector.reflector())
+           .plugboard(plugboard)
+           .window_positions(&windows[..count]))
+}
+
+/// Writes out the key string for a machine, with the letters now showing in its windows and the
+/// plugboard pairs in alphabetical order.
+///
+/// Only machines made from the parts a key string can name are written out: any other gives an
+/// `Error::Machine`.
+#[cfg(feature = "alloc")]
+pub fn format(enigma: &Enigma) -> Result<String, Error> {
+    if enigma.entry_wheel().is_some() || enigma.stepping() != Stepping::Ratchet ||
+       enigma.reflector_position() != Alpha::A {
+        return Err(Error::Machine("Key strings cannot describe this model"));
+    }
+    let reflector = reflector_spec(|spec| spec.reflector() == enigma.reflector())
+        .ok_or(Error::Machine("Key strings cannot name this reflector"))?;
+
+    let slots = 0..enigma.rotor_count();
+    let fixed = slots.clone().filter(|&slot| !enigma.rotor_steps(slot)).count();
+    if fixed > 1 || is_thin(reflector) != (fixed == 1) {
+        return Err(Error::Machine("Key strings cannot describe this model"));
+    }
+    let mut key = String::from(reflector.name);
+    for slot in slots.clone() {
+        let rotor = enigma.rotor(slot);
+        let fixed = !enigma.rotor_steps(slot);
+        let spec = wheels(fixed).find(|spec| rotor.is_from(&spec.factory()))
+            .ok_or(Error::Machine("Key strings cannot name this rotor"))?;
+        if fixed && slot > 0 && enigma.rotor_steps(slot - 1) {
+            return Err(Error::Machine("Key strings cannot describe this model"));
+        }
+        write!(key, " {}", spec.name).unwrap();
+    }
+    key.push_str(" /");
+    for slot in slots.clone() {
+        write!(key, " {:02}", enigma.rotor(slot).ring_setting() + 1).unwrap();
+    }
+    key.push_str(" /");
+    for (a, b) in enigma.plugboard().pairs() {
+        write!(key, " {}{}", a, b).unwrap();
+    }
+    key.push_str(" / ");
+    for slot in slots {
+        write!(key, "{}", enigma.window_position(slot)).unwrap();
+    }
+    Ok(key)
+}
+
+/// The stepping rotors or, if `fixed`, the Greek wheels a key string can name.
+fn wheels(fixed: bool) -> impl Iterator<Item = &'static WheelSpec> {
+    MODELS.iter().flat_map(move |model| if fixed { model.fixed_rotors() } else { model.rotors() })
+}
+
+fn rotor_spec(name: &str, fixed: bool) -> Option<&'static WheelSpec> {
+    wheels(fixed).find(|spec| spec.name == name)
+}
+
+fn reflector_spec<P>(predicate: P) -> Option<&'static ReflectorSpec>
+    where P: FnMut(&&'static ReflectorSpec) -> bool
+{
+    MODELS.iter().flat_map(|model| model.reflectors()).find(predicate)
+}
+
+/// Whether `spec` is one of the M4's thin reflectors, which only fit alongside a Greek wheel.
+fn is_thin(spec: &ReflectorSpec) -> bool {
+    MachineModel::M4.reflectors().contains(spec)
+}
+
+/// Turns the parts of a key string into errors which point back into it.
+struct Parser<'a> {
+    key: &'a str,
+}
+
+impl<'a> Parser<'a> {
+    /// An error for `token`, which must be a slice of the key string.
+    fn error(&self, message: &'static str, token: &str) -> Error {
+        let start = token.as_ptr() as usize - self.key.as_ptr() as usize;
+        Error::Key(KeyError::new(message, start..start + token.len()))
+    }
+
+    /// An error for something missing from `part`, pointing just after its last token.
+    fn missing(&self, message: &'static str, part: &str) -> Error {
+        let part = part.trim_end();
+        self.error(message, &part[part.len()..])
+    }
+
+    /// Exactly `count` values read from `part`.
+    fn exactly<'b, I>(&self, values: I, part: &'b str, count: usize, wrong_count: &'static str)
+                      -> Result<[Alpha; MAX_ROTORS], Error>
+        where I: Iterator<Item = Token<'b, Alpha>>
+    {
+        let mut letters = [Alpha::A; MAX_ROTORS];
+        let mut i = 0;
+        for (token, letter) in values {
+            let letter = letter.map_err(|message| self.error(message, token))?;
+            if i == count {
+                return Err(self.error(wrong_count, token));
+            }
+            letters[i] = letter;
+            i += 1;
+        }
+        if i != count {
+            return Err(self.missing(wrong_count, part));
+        }
+        Ok(letters)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alpha::Alpha::*;
+    use reflector::Reflectors;
+    use rotor::Rotors;
+
+    #[test]
+    fn parses_key() {
+        let key = "B II IV V / 02 21 12 / AV BS CG DL FU HZ IN KM OW RX / BLA";
+        let mut enigma = parse(key).unwrap().build().unwrap();
+        assert_eq!(3, enigma.rotor_count());
+        assert_eq!([B, L, A], [0, 1, 2].map(|i| enigma.window_position(i)));
+        assert_eq!(23, enigma.plugboard().sub(usize::from(R)));
+
+        let rotors = Rotors::default();
+        let mut expected = EnigmaBuilder::default()
+            .left_rotor(rotors.ii.with_ring_setting(B))
+            .mid_rotor(rotors.iv.with_ring_setting(U))
+            .right_rotor(rotors.v.with_ring_setting(L))
+            .reflector(Reflectors::default().b)
+            .plugboard(*enigma.plugboard())
+            .window_positions([B, L, A])
+            .build()
+            .unwrap();
+        for l in Alpha::all() {
+            assert_eq!(expected.press(l), enigma.press(l));
+        }
+    }
+
+    #[test]
+    fn parses_naval_keys() {
+        let m4 = MachineModel::M4;
+        let key = "B thin Beta II IV I / 01 01 01 22 / AT BL DF GJ HM NW OP QY RZ VX / VJNA";
+        let mut enigma = parse(key).unwrap().build().unwrap();
+        assert_eq!(4, enigma.rotor_count());
+        assert!(!enigma.rotor_steps(0));
+        let mut expected = EnigmaBuilder::for_model(m4)
+            .fixed_rotors([m4.rotor("Beta").unwrap().with_ring_setting(A)])
+            .rotors([m4.rotor("II").unwrap().with_ring_setting(A),
+                     m4.rotor("IV").unwrap().with_ring_setting(A),
+                     m4.rotor("I").unwrap().with_ring_setting(V)])
+            .reflector(m4.reflector("B thin").unwrap())
+            .plugboard(*enigma.plugboard())
+            .window_positions([V, J, N, A])
+            .build()
+            .unwrap();
+        for l in Alpha::all() {
+            assert_eq!(expected.press(l), enigma.press(l));
+        }
+
+        let enigma = parse("B III VI VIII / 01 08 13 / AN EZ HK IJ LR MQ OT PV SW UX / UZV")
+            .unwrap()
+            .build()
+            .unwrap();
+        assert!(enigma.rotor(1).is_from(&MachineModel::M3.rotor("VI").unwrap()));
+    }
+
+    #[test]
+    fn letter_ring_settings_and_empty_plugboard() {
+        let mut numbers = parse("C I II III / 01 01 26 / / AAA").unwrap().build().unwrap();
+        let mut letters = parse("C I II III / A A Z / / A A A").unwrap().build().unwrap();
+        for l in Alpha::all() {
+            assert_eq!(numbers.press(l), letters.press(l));
+        }
+    }
+
+    #[test]
+    fn invalid_keys() {
+        let error = |key| match parse(key).unwrap_err() {
+            Error::Key(e) => (e.message(), e.token(key)),
+            e => panic!("{}", e),
+        };
+        assert_eq!(("Unknown reflector", "D"), error("D I II III / 01 01 01 / / AAA"));
+        assert_eq!(("Unknown reflector", "A  thin"), error("A  thin I II / 01 01 / / AA"));
+        assert_eq!(("Unknown rotor", "IX"), error("B I II IX / 01 01 01 / / AAA"));
+        assert_eq!(("Greek wheels must come first", "Beta"),
+                   error("B thin I Beta II III / 01 01 01 01 / / AAAA"));
+        assert_eq!(("Must supply at least one rotor", ""), error("B thin Beta / 01 / / A"));
+        assert_eq!(("Only one Greek wheel can be fitted", "Gamma"),
+                   error("B thin Beta Gamma II III / 01 01 01 01 / / AAAA"));
+        assert_eq!(("A thin reflector needs a Greek wheel", "C thin"),
+                   error("C thin I II III / 01 01 01 / / AAA"));
+        assert_eq!(("A Greek wheel needs a thin reflector", "Beta"),
+                   error("B Beta I II III / 01 01 01 01 / / AAAA"));
+        assert_eq!(("Ring settings must be from 01 to 26", "27"),
+                   error("B I II III / 01 01 27 / / AAA"));
+        assert_eq!(("Must supply a ring setting for each rotor", "01"),
+                   error("B I II / 01 01 01 / / AA"));
+        assert_eq!(("Must only contain letters", "1"), error("B I II III / A A 1 / / AAA"));
+        assert_eq!(("Plugboard pairs must be two letters", "ABC"),
+                   error("B I II III / 01 01 01 / AB ABC / AAA"));
+        assert_eq!(("Must supply a window position for each rotor", "A"),
+                   error("B I II III / 01 01 01 / / AAAA"));
+        assert_eq!(("Must have four parts separated by '/'", "AAA"),
+                   error("B I II III / 01 01 01 / / / AAA"));
+
+        assert_eq!(Error::Plugboard("Each letter can only be plugged once"),
+                   parse("B I II III / 01 01 01 / AB AC / AAA").unwrap_err());
+    }
+
+    #[test]
+    fn missing_tokens_point_to_where_they_belong() {
+        let span = |key| match parse(key).unwrap_err() {
+            Error::Key(e) => e.span(),
+            e => panic!("{}", e),
+        };
+        assert_eq!(27..27, span("B I II III / 01 01 01 / AAA"));
+        assert_eq!(18..18, span("B I II III / 01 01 / / AAA"));
+        assert_eq!(0..0, span(" / 01 01 01 / / AAA"));
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn formats_key() {
+        let key = "B II IV V / 02 21 12 / AV BS CG DL FU HZ IN KM OW RX / BLA";
+        let mut enigma = parse(key).unwrap().build().unwrap();
+        assert_eq!(key, format(&enigma).unwrap());
+
+        enigma.press(A);
+        assert_eq!("B II IV V / 02 21 12 / AV BS CG DL FU HZ IN KM OW RX / BLB",
+                   format(&enigma).unwrap());
+
+        let letters = parse("C I II III / A A Z / XA / A A A").unwrap().build().unwrap();
+        assert_eq!("C I II III / 01 01 26 / AX / AAA", format(&letters).unwrap());
+
+        for key in &["B thin Beta II IV I / 01 01 01 22 / AT BL DF GJ HM NW OP QY RZ VX / VJNA",
+                     "C thin Gamma VIII VII VI / 02 03 04 05 / / ABCD",
+                     "B III VI VIII / 01 08 13 / AN EZ HK IJ LR MQ OT PV SW UX / UZV",
+                     "A I II III / 01 01 01 / / AAA"] {
+            assert_eq!(*key, format(&parse(key).unwrap().build().unwrap()).unwrap());
+        }
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn cannot_format_other_machines() {
+        let t = MachineModel::T;
+        let enigma = EnigmaBuilder::for_model(t)
+            .rotors(["I", "II", "III"].map(|n| t.rotor(n).unwrap().with_ring_setting(A)))
+            .reflector(t.reflector("UKW").unwrap())
+            .window_positions([A, A, A])
+            .build()
+            .unwrap();
+        assert_eq!(Err(Error::Machine("Key strings cannot describe this model")), format(&enigma));
+
+        let rotors = Rotors::default();
+        let thin = EnigmaBuilder::default()
+            .rotors([rotors.i, rotors.ii, rotors.iii].map(|r| r.with_ring_setting(A)))
+            .reflector(MachineModel::M4.reflector("B thin").unwrap())
+            .window_positions([A, A, A])
+            .build()
+            .unwrap();
+        assert_eq!(Err(Error::Machine("Key strings cannot describe this model")), format(&thin));
+
+        let enigma = EnigmaBuilder::default()
+            .rotors([Rotors::default().i.with_ring_setting(A).reversed()])
+            .reflector(Reflectors::default().b)
+            .window_positions([A])
+            .build()
+            .unwrap();
+        assert_eq!(Err(Error::Machine("Key strings cannot name this rotor")), format(&enigma));
+    }
+}
@@ -4,21 +4,103 @@
         unused_extern_crates,
         unused_import_braces, unused_qualifications)]
 
+#![no_std]
+
 #![cfg_attr(feature = "dev", allow(unstable_features))]
 #![cfg_attr(feature = "dev", feature(plugin))]
 #![cfg_attr(feature = "dev", plugin(clippy))]
 
+#[cfg(any(feature = "alloc", test))]
+#[macro_use]
+extern crate alloc;
+#[cfg(feature = "std")]
+extern crate std;
+#[cfg(feature = "wasm")]
+extern crate wasm_bindgen;
+#[cfg(feature = "python")]
+extern crate pyo3;
+
 pub use self::alpha::Alpha;
+#[cfg(feature = "alloc")]
 pub use self::alpha::to_string as alphas_to_string;
+pub use self::alpha::InvalidCharacter;
+pub use self::alpha::NonAlphabetic;
+pub use self::alpha::Symbol;
+pub use self::enigma::Enigma;
 pub use self::enigma::EnigmaBuilder;
+pub use self::enigma::MAX_ROTORS;
+pub use self::rotor::Orientation;
+pub use self::rotor::Rotor;
+pub use self::rotor::RotorFactory;
 pub use self::rotor::Rotors;
+pub use self::reflector::Reflector;
 pub use self::reflector::Reflectors;
+pub use self::plugboard::Plugboard;
+pub use self::entry_wheel::EntryWheel;
+pub use self::trace::Component;
+pub use self::trace::Hop;
+#[cfg(feature = "alloc")]
+pub use self::trace::Trace;
+pub use self::typex::TypexBuilder;
+pub use self::model::MachineModel;
+pub use self::model::ReflectorSpec;
+pub use self::model::Stepping;
+pub use self::model::WheelSpec;
+pub use self::error::Error;
+pub use self::error::KeyError;
+#[cfg(feature = "alloc")]
+pub use self::cycle::Cycle;
+pub use self::observer::Event;
+pub use self::observer::Observer;
+pub use self::state::Difference;
+pub use self::state::MachineState;
 
 mod enigma;
+mod trace;
+mod typex;
+mod model;
+#[cfg(feature = "alloc")]
+mod cycle;
+mod state;
+mod observer;
 
 mod rotor;
 mod reflector;
+mod plugboard;
+mod entry_wheel;
 
 mod cipher;
 
 mod alpha;
+
+mod error;
+mod tokens;
+#[cfg(feature = "alloc")]
+mod random;
+pub mod key;
+
+#[cfg(feature = "std")]
+pub mod scoring;
+#[cfg(feature = "std")]
+pub mod banburismus;
+#[cfg(feature = "std")]
+pub mod ring_recovery;
+#[cfg(feature = "std")]
+pub mod hill_climbing;
+#[cfg(feature = "alloc")]
+pub mod rodding;
+#[cfg(feature = "alloc")]
+pub mod wiring_recovery;
+#[cfg(feature = "alloc")]
+pub mod vectors;
+#[cfg(feature = "alloc")]
+pub mod traffic;
+#[cfg(feature = "alloc")]
+pub mod corpus;
+
+#[cfg(feature = "wasm")]
+pub mod wasm;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "python")]
+pub mod python;
@@ -0,0 +1,357 @@
+use entry_wheel::EntryWheel;
+use reflector::Reflector;
+use rotor::RotorFactory;
+
+/// How a key press turns the rotors.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Default)]
+pub enum Stepping {
+    /// Pawls and ratchets, as on most Enigmas. Each pawl pushes on the rotor to its left as well
+    /// as its own, which gives the 'double step' of the middle rotor.
+    #[default]
+    Ratchet,
+    /// Gear wheels, as on the Enigma G. The rotors turn like an odometer, with no double step,
+    /// and the reflector is turned by the left-hand rotor as the next wheel along.
+    Gear,
+}
+
+/// A rotor as issued with a machine.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct WheelSpec {
+    pub name: &'static str,
+    /// The 26 letters that `A-Z` are wired to.
+    pub wiring: &'static str,
+    /// The letters showing in the window when the rotor turns over the rotor to its left.
+    pub turnovers: &'static str,
+}
+
+impl WheelSpec {
+    pub fn factory(&self) -> RotorFactory {
+        RotorFactory::new(self.wiring, self.turnovers)
+    }
+}
+
+/// A reflector as issued with a machine.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct ReflectorSpec {
+    pub name: &'static str,
+    /// The 26 letters that `A-Z` are wired to.
+    pub wiring: &'static str,
+}
+
+impl ReflectorSpec {
+    pub fn reflector(&self) -> Reflector {
+        Reflector::from_wiring(self.wiring)
+    }
+}
+
+/// The machines whose wiring is known, with the parts that were issued with each.
+///
+/// Use `EnigmaBuilder::for_model` to build one, which checks that only its own parts are used.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash)]
+pub enum MachineModel {
+    /// The Wehrmacht and Luftwaffe Enigma.
+    EnigmaI,
+    /// The Kriegsmarine's three-rotor Enigma.
+    M3,
+    /// The Kriegsmarine's four-rotor Enigma, with a thin reflector and a Greek wheel.
+    M4,
+    /// The Enigma I used by the Norwegian police after the war, with its own wiring.
+    Norway,
+    /// A special Enigma I, captured in 1944, with its own wiring.
+    Sondermaschine,
+    /// The commercial Enigma of 1926.
+    D,
+    /// The later commercial Enigma.
+    K,
+    /// The Enigma K used by the Swiss army, with its own wiring.
+    SwissK,
+    /// The Enigma K used by the Reichsbahn, with its own wiring.
+    Railway,
+    /// The Abwehr's counter Enigma G-312, with gear stepping and many notches.
+    G312,
+    /// The Enigma G-111, used in Hungary and Munich.
+    G111,
+    /// The Enigma T ('Tirpitz') made for Japan, with its own entry wheel, eight rotors with five
+    /// turnovers each, and a settable reflector.
+    T,
+}
+
+const QWERTZ: &str = "QWERTZUIOASDFGHJKPYXCVBNML";
+
+const COMMERCIAL_REFLECTOR: ReflectorSpec = ReflectorSpec {
+    name: "UKW",
+    wiring: "IMETCGFRAYSQBZXWLHKDVUPOJN",
+};
+
+const ENIGMA_I_ROTORS: [WheelSpec; 5] = [
+    WheelSpec { name: "I", wiring: "EKMFLGDQVZNTOWYHXUSPAIBRCJ", turnovers: "Q" },
+    WheelSpec { name: "II", wiring: "AJDKSIRUXBLHWTMCQGZNPYFVOE", turnovers: "E" },
+    WheelSpec { name: "III", wiring: "BDFHJLCPRTXVZNYEIWGAKMUSQO", turnovers: "V" },
+    WheelSpec { name: "IV", wiring: "ESOVPZJAYQUIRHXLNFTGKDCMWB", turnovers: "J" },
+    WheelSpec { name: "V", wiring: "VZBRGITYUPSDNHLXAWMJQOFECK", turnovers: "Z" },
+];
+
+const NAVAL_ROTORS: [WheelSpec; 8] = [
+    ENIGMA_I_ROTORS[0],
+    ENIGMA_I_ROTORS[1],
+    ENIGMA_I_ROTORS[2],
+    ENIGMA_I_ROTORS[3],
+    ENIGMA_I_ROTORS[4],
+    WheelSpec { name: "VI", wiring: "JPGVOUMFYQBENHZRDKASXLICTW", turnovers: "ZM" },
+    WheelSpec { name: "VII", wiring: "NZJHGRCXMYSWBOUFAIVLPEKQDT", turnovers: "ZM" },
+    WheelSpec { name: "VIII", wiring: "FKQHTLXOCBJSPDZRAMEWNIUYGV", turnovers: "ZM" },
+];
+
+const ENIGMA_I_REFLECTORS: [ReflectorSpec; 3] = [
+    ReflectorSpec { name: "A", wiring: "EJMZALYXVBWFCRQUONTSPIKHGD" },
+    ReflectorSpec { name: "B", wiring: "YRUHQSLDPXNGOKMIEBFZCWVJAT" },
+    ReflectorSpec { name: "C", wiring: "FVPJIAOYEDRZXWGCTKUQSBNMHL" },
+];
+
+const GREEK_WHEELS: [WheelSpec; 2] = [
+    WheelSpec { name: "Beta", wiring: "LEYJVCNIXWPBQMDRTAKZGFUHOS", turnovers: "" },
+    WheelSpec { name: "Gamma", wiring: "FSOKANUERHMBTIYCWLQPZXVGJD", turnovers: "" },
+];
+
+const THIN_REFLECTORS: [ReflectorSpec; 2] = [
+    ReflectorSpec { name: "B thin", wiring: "ENKQAUYWJICOPBLMDXZVFTHRGS" },
+    ReflectorSpec { name: "C thin", wiring: "RDOBJNTKVEHMLFCWZAXGYIPSUQ" },
+];
+
+const NORWAY_ROTORS: [WheelSpec; 5] = [
+    WheelSpec { name: "I", wiring: "WTOKASUYVRBXJHQCPZEFMDINLG", turnovers: "Q" },
+    WheelSpec { name: "II", wiring: "GJLPUBSWEMCTQVHXAOFZDRKYNI", turnovers: "E" },
+    WheelSpec { name: "III", wiring: "JWFMHNBPUSDYTIXVZGRQLAOEKC", turnovers: "V" },
+    WheelSpec { name: "IV", wiring: "FGZJMVXEPBWSHQTLIUDYKCNRAO", turnovers: "J" },
+    WheelSpec { name: "V", wiring: "HEJXQOTZBVFDASCILWPGYNMURK", turnovers: "Z" },
+];
+
+const NORWAY_REFLECTORS: [ReflectorSpec; 1] = [
+    ReflectorSpec { name: "UKW", wiring: "MOWJYPUXNDSRAIBFVLKZGQCHET" },
+];
+
+const SONDERMASCHINE_ROTORS: [WheelSpec; 3] = [
+    WheelSpec { name: "I", wiring: "VEOSIRZUJDQCKGWYPNXAFLTHMB", turnovers: "Q" },
+    WheelSpec { name: "II", wiring: "UEMOATQLSHPKCYFWJZBGVXIDNR", turnovers: "E" },
+    WheelSpec { name: "III", wiring: "TZHXMBSIPNURJFDKEQVCWGLAOY", turnovers: "V" },
+];
+
+const SONDERMASCHINE_REFLECTORS: [ReflectorSpec; 1] = [
+    ReflectorSpec { name: "UKW", wiring: "CIAGSNDRBYTPZFULVHEKOQXWJM" },
+];
+
+const COMMERCIAL_ROTORS: [WheelSpec; 3] = [
+    WheelSpec { name: "I", wiring: "LPGSZMHAEOQKVXRFYBUTNICJDW", turnovers: "Y" },
+    WheelSpec { name: "II", wiring: "SLVGBTFXJQOHEWIRZYAMKPCNDU", turnovers: "E" },
+    WheelSpec { name: "III", wiring: "CJGDPSHKTURAWZXFMYNQOBVLIE", turnovers: "N" },
+];
+
+const SWISS_K_ROTORS: [WheelSpec; 3] = [
+    WheelSpec { name: "I", wiring: "PEZUOHXSCVFMTBGLRINQJWAYDK", turnovers: "Y" },
+    WheelSpec { name: "II", wiring: "ZOUESYDKFWPCIQXHMVBLGNJRAT", turnovers: "E" },
+    WheelSpec { name: "III", wiring: "EHRVXGAOBQUSIMZFLYNWKTPDJC", turnovers: "N" },
+];
+
+const RAILWAY_ROTORS: [WheelSpec; 3] = [
+    WheelSpec { name: "I", wiring: "JGDQOXUSCAMIFRVTPNEWKBLZYH", turnovers: "N" },
+    WheelSpec { name: "II", wiring: "NTZPSFBOKMWRCJDIVLAEYUXHGQ", turnovers: "E" },
+    WheelSpec { name: "III", wiring: "JVIUBHTCDYAKEQZPOSGXNRMWFL", turnovers: "Y" },
+];
+
+const RAILWAY_REFLECTORS: [ReflectorSpec; 1] = [
+    ReflectorSpec { name: "UKW", wiring: "QYHOGNECVPUZTFDJAXWMKISRBL" },
+];
+
+const G312_ROTORS: [WheelSpec; 3] = [
+    WheelSpec { name: "I", wiring: "DMTWSILRUYQNKFEJCAZBPGXOHV", turnovers: "SUVWZABCEFGIKLOPQ" },
+    WheelSpec { name: "II", wiring: "HQZGPJTMOBLNCIFDYAWVEUSRKX", turnovers: "STVYZACDFGHKMNQ" },
+    WheelSpec { name: "III", wiring: "UQNTLSZFMREHDPXKIBVYGJCWOA", turnovers: "UWXAEFHKMNR" },
+];
+
+const G312_REFLECTORS: [ReflectorSpec; 1] = [
+    ReflectorSpec { name: "UKW", wiring: "RULQMZJSYGOCETKWDAHNBXPVIF" },
+];
+
+const G111_ROTORS: [WheelSpec; 3] = [
+    WheelSpec { name: "I", wiring: "WLRHBQUNDKJCZSEXOTMAGYFPVI", turnovers: "SUVWZABCEFGIKLOPQ" },
+    WheelSpec { name: "II", wiring: "TFJQAZWMHLCUIXRDYGOEVBNSKP", turnovers: "STVYZACDFGHKMNQ" },
+    WheelSpec { name: "V", wiring: "QTPIXWVDFRMUSLJOHCANEZKYBG", turnovers: "SWZFHMQ" },
+];
+
+const T_ROTORS: [WheelSpec; 8] = [
+    WheelSpec { name: "I", wiring: "KPTYUELOCVGRFQDANJMBSWHZXI", turnovers: "WZEKQ" },
+    WheelSpec { name: "II", wiring: "UPHZLWEQMTDJXCAKSOIGVBYFNR", turnovers: "WZFLR" },
+    WheelSpec { name: "III", wiring: "QUDLYRFEKONVZAXWHMGPJBSICT", turnovers: "WZEKQ" },
+    WheelSpec { name: "IV", wiring: "CIWTBKXNRESPFLYDAGVHQUOJZM", turnovers: "WZFLR" },
+    WheelSpec { name: "V", wiring: "UAXGISNJBVERDYLFZWTPCKOHMQ", turnovers: "YCFKR" },
+    WheelSpec { name: "VI", wiring: "XFUZGALVHCNYSEWQTDMRBKPIOJ", turnovers: "XEIMQ" },
+    WheelSpec { name: "VII", wiring: "BJVFTXPLNAYOZIKWGDQERUCHSM", turnovers: "YCFKR" },
+    WheelSpec { name: "VIII", wiring: "YMTPNZHWKODAJXELUQVGCBISFR", turnovers: "XEIMQ" },
+];
+
+const T_REFLECTORS: [ReflectorSpec; 1] = [
+    ReflectorSpec { name: "UKW", wiring: "GEKPBTAUMOCNILJDXZYFHWVQSR" },
+];
+
+impl MachineModel {
+    /// Every model, in the order they are listed above.
+    pub fn all() -> [MachineModel; 12] {
+        use self::MachineModel::*;
+        [EnigmaI, M3, M4, Norway, Sondermaschine, D, K, SwissK, Railway, G312, G111, T]
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            MachineModel::EnigmaI => "Enigma I",
+            MachineModel::M3 => "Enigma M3",
+            MachineModel::M4 => "Enigma M4",
+            MachineModel::Norway => "Norway Enigma",
+            MachineModel::Sondermaschine => "Sondermaschine",
+            MachineModel::D => "Enigma D",
+            MachineModel::K => "Enigma K",
+            MachineModel::SwissK => "Swiss-K",
+            MachineModel::Railway => "Railway Enigma",
+            MachineModel::G312 => "Enigma G-312",
+            MachineModel::G111 => "Enigma G-111",
+            MachineModel::T => "Enigma T",
+        }
+    }
+
+    /// The stepping rotors issued with the machine.
+    pub fn rotors(self) -> &'static [WheelSpec] {
+        match self {
+            MachineModel::EnigmaI => &ENIGMA_I_ROTORS,
+            MachineModel::M3 | MachineModel::M4 => &NAVAL_ROTORS,
+            MachineModel::Norway => &NORWAY_ROTORS,
+            MachineModel::Sondermaschine => &SONDERMASCHINE_ROTORS,
+            MachineModel::D | MachineModel::K => &COMMERCIAL_ROTORS,
+            MachineModel::SwissK => &SWISS_K_ROTORS,
+            MachineModel::Railway => &RAILWAY_ROTORS,
+            MachineModel::G312 => &G312_ROTORS,
+            MachineModel::G111 => &G111_ROTORS,
+            MachineModel::T => &T_ROTORS,
+        }
+    }
+
+    /// The wheels which sit, without stepping, to the left of the stepping rotors: the M4's
+    /// Greek wheels.
+    pub fn fixed_rotors(self) -> &'static [WheelSpec] {
+        match self {
+            MachineModel::M4 => &GREEK_WHEELS,
+            _ => &[],
+        }
+    }
+
+    pub fn reflectors(self) -> &'static [ReflectorSpec] {
+        match self {
+            MachineModel::EnigmaI => &ENIGMA_I_REFLECTORS,
+            MachineModel::M3 => &ENIGMA_I_REFLECTORS[1..],
+            MachineModel::M4 => &THIN_REFLECTORS,
+            MachineModel::Norway => &NORWAY_REFLECTORS,
+            MachineModel::Sondermaschine => &SONDERMASCHINE_REFLECTORS,
+            MachineModel::D | MachineModel::K | MachineModel::SwissK | MachineModel::G111 => {
+                &[COMMERCIAL_REFLECTOR]
+            }
+            MachineModel::Railway => &RAILWAY_REFLECTORS,
+            MachineModel::G312 => &G312_REFLECTORS,
+            MachineModel::T => &T_REFLECTORS,
+        }
+    }
+
+    /// The entry wheel, or `None` for machines which wire the keys to the rotors alphabetically.
+    pub fn entry_wheel(self) -> Option<EntryWheel> {
+        match self {
+            MachineModel::EnigmaI |
+            MachineModel::M3 |
+            MachineModel::M4 |
+            MachineModel::Norway |
+            MachineModel::Sondermaschine => None,
+            MachineModel::T => Some(EntryWheel::from_wiring("KZROUQHYAIGBLWVSTDXFPNMCJE")),
+            _ => Some(EntryWheel::from_wiring(QWERTZ)),
+        }
+    }
+
+    pub fn stepping(self) -> Stepping {
+        match self {
+            MachineModel::G312 | MachineModel::G111 => Stepping::Gear,
+            _ => Stepping::Ratchet,
+        }
+    }
+
+    /// The number of rotor slots, including the slot for a fixed wheel.
+    pub fn slots(self) -> usize {
+        3 + self.fixed_slots()
+    }
+
+    /// The number of slots for wheels which do not step.
+    pub fn fixed_slots(self) -> usize {
+        match self {
+            MachineModel::M4 => 1,
+            _ => 0,
+        }
+    }
+
+    /// Whether the machine has a plugboard. Only the military models do.
+    pub fn has_plugboard(self) -> bool {
+        matches!(self,
+                 MachineModel::EnigmaI |
+                 MachineModel::M3 |
+                 MachineModel::M4 |
+                 MachineModel::Norway |
+                 MachineModel::Sondermaschine)
+    }
+
+    /// Whether the reflector can be turned to a starting position, as on the commercial models.
+    pub fn settable_reflector(self) -> bool {
+        !self.has_plugboard()
+    }
+
+    /// Looks up a rotor or fixed wheel by name, e.g. `"VI"` or `"Beta"`.
+    pub fn rotor(self, name: &str) -> Option<RotorFactory> {
+        self.rotors()
+            .iter()
+            .chain(self.fixed_rotors())
+            .find(|w| w.name == name)
+            .map(WheelSpec::factory)
+    }
+
+    /// Looks up a reflector by name, e.g. `"B"`.
+    pub fn reflector(self, name: &str) -> Option<Reflector> {
+        self.reflectors().iter().find(|r| r.name == name).map(ReflectorSpec::reflector)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cipher;
+    use cipher::is_pairs;
+
+    #[test]
+    fn catalogue_is_well_formed() {
+        for model in MachineModel::all().iter() {
+            for wheel in model.rotors().iter().chain(model.fixed_rotors()) {
+                // Panics if the wiring is not a permutation
+                wheel.factory();
+            }
+            for reflector in model.reflectors() {
+                assert!(is_pairs(cipher::from_string(reflector.wiring)),
+                        "{} reflector {} must be in pairs",
+                        model.name(),
+                        reflector.name);
+            }
+            assert!(!model.reflectors().is_empty());
+            assert!(model.rotors().len() >= 3 - model.fixed_slots());
+        }
+    }
+
+    #[test]
+    fn lookup() {
+        assert!(MachineModel::M4.rotor("Beta").is_some());
+        assert!(MachineModel::M3.rotor("Beta").is_none());
+        assert!(MachineModel::EnigmaI.rotor("VI").is_none());
+        assert!(MachineModel::EnigmaI.reflector("A").is_some());
+        assert!(MachineModel::M3.reflector("A").is_none());
+        assert_eq!(Stepping::Gear, MachineModel::G312.stepping());
+    }
+}
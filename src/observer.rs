@@ -0,0 +1,69 @@
+use core::fmt;
+
+use alpha::Alpha;
+
+/// Something which happened inside the machine, as seen by an `Observer`.
+///
+/// Rotors are numbered by slot, from the left starting at 0.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Event {
+    /// The notch of the rotor in `slot` is engaged, so it will carry the next stepping rotor to
+    /// its left (or, with gear stepping, the reflector).
+    Turnover { slot: usize },
+    /// The rotor in `slot` is stepping on its own notch as well as the one to its right: the
+    /// double step of a ratchet-driven machine.
+    DoubleStep { slot: usize },
+    /// The rotor in `slot` stepped, and now shows `window`.
+    Step { slot: usize, window: Alpha },
+    /// The reflector stepped, and is now at `position`.
+    ReflectorStep { position: Alpha },
+    /// `key` was pressed and `lamp` lit up.
+    Lamp { key: Alpha, lamp: Alpha },
+    /// The machine was returned to its starting positions.
+    Reset,
+}
+
+/// Watches a machine as keys are pressed, via `Enigma::press_observed`. Any `FnMut(Event)` closure
+/// is an observer.
+///
+/// For each key press, the events the observer sees depend on how the machine steps. `Lamp` always
+/// comes last.
+///
+/// - `Stepping::Ratchet`: first a `Turnover` for each engaged notch, from left to right. Each is
+///   followed by a `DoubleStep` unless it belongs to the right-most stepping rotor. Then comes a
+///   `Step` for each rotor that moves, from left to right. The reflector never steps.
+/// - `Stepping::Gear`: the rotors are visited from right to left. Each one that is reached sends a
+///   `Turnover` if its notch is engaged, then its `Step`. The first rotor without an engaged notch
+///   stops the carry. If the left-most stepping rotor carries, a `ReflectorStep` follows its
+///   `Step`. There are no double steps.
+pub trait Observer {
+    fn notify(&mut self, event: Event);
+}
+
+impl<F: FnMut(Event)> Observer for F {
+    fn notify(&mut self, event: Event) {
+        self(event)
+    }
+}
+
+/// The observer used by `Enigma::press`. It does nothing, so the calls to it compile away.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct Unobserved;
+
+impl Observer for Unobserved {
+    #[inline(always)]
+    fn notify(&mut self, _: Event) {}
+}
+
+impl fmt::Display for Event {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Event::Turnover { slot } => write!(f, "Rotor {} turns over", slot + 1),
+            Event::DoubleStep { slot } => write!(f, "Rotor {} double steps", slot + 1),
+            Event::Step { slot, window } => write!(f, "Rotor {} steps to {}", slot + 1, window),
+            Event::ReflectorStep { position } => write!(f, "Reflector steps to {}", position),
+            Event::Lamp { key, lamp } => write!(f, "{} lights {}", key, lamp),
+            Event::Reset => write!(f, "Reset"),
+        }
+    }
+}
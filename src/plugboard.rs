@@ -0,0 +1,98 @@
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+use alpha::Alpha;
+use cipher::Cipher;
+use cipher::CipherChar;
+
+/// The Plugboard ('Steckerbrett') swaps pairs of letters on the way into and out of the rotors.
+///
+/// Each cable connects two letters, so the substitution is its own inverse. Letters without a
+/// cable pass through unchanged.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct Plugboard {
+    cipher: Cipher,
+}
+
+impl Default for Plugboard {
+    fn default() -> Self {
+        let mut cipher = [0; 26];
+        for (i, v) in cipher.iter_mut().enumerate() {
+            *v = i;
+        }
+        Plugboard { cipher }
+    }
+}
+
+impl Plugboard {
+    pub fn new(pairs: &[(Alpha, Alpha)]) -> Result<Plugboard, &'static str> {
+        let mut plugboard = Plugboard::default();
+        for &(a, b) in pairs {
+            let (a, b) = (usize::from(a), usize::from(b));
+            if a == b {
+                return Err("Cannot plug a letter into itself");
+            }
+            if plugboard.cipher[a] != a || plugboard.cipher[b] != b {
+                return Err("Each letter can only be plugged once");
+            }
+            plugboard.cipher[a] = b;
+            plugboard.cipher[b] = a;
+        }
+        Ok(plugboard)
+    }
+
+    pub fn sub(&self, c: CipherChar) -> CipherChar {
+        self.cipher[c]
+    }
+
+    /// The plugged pairs, each with the alphabetically lower letter first.
+    #[cfg(feature = "alloc")]
+    pub fn pairs(&self) -> Vec<(Alpha, Alpha)> {
+        let mut pairs = Vec::new();
+        for (i, &v) in self.cipher.iter().enumerate() {
+            if i < v {
+                pairs.push((Alpha::try_from_usize(i).unwrap(), Alpha::try_from_usize(v).unwrap()));
+            }
+        }
+        pairs
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alpha::Alpha::*;
+    use cipher::from_char as CC;
+
+    #[test]
+    fn empty_plugboard() {
+        let plugboard = Plugboard::default();
+
+        assert_eq!(CC('A'), plugboard.sub(CC('A')));
+        assert_eq!(CC('Z'), plugboard.sub(CC('Z')));
+    }
+
+    #[test]
+    fn swapped_pairs() {
+        let plugboard = Plugboard::new(&[(A, B), (Z, Y)]).unwrap();
+
+        assert_eq!(CC('B'), plugboard.sub(CC('A')));
+        assert_eq!(CC('A'), plugboard.sub(CC('B')));
+        assert_eq!(CC('Y'), plugboard.sub(CC('Z')));
+        assert_eq!(CC('C'), plugboard.sub(CC('C')));
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn pairs() {
+        assert!(Plugboard::default().pairs().is_empty());
+        let plugboard = Plugboard::new(&[(A, B), (Z, Y)]).unwrap();
+        assert_eq!(vec![(A, B), (Y, Z)], plugboard.pairs());
+    }
+
+    #[test]
+    fn letter_plugged_twice() {
+        assert!(Plugboard::new(&[(A, B), (B, C)]).is_err());
+        assert!(Plugboard::new(&[(A, A)]).is_err());
+    }
+}
@@ -0,0 +1,749 @@
+//! Python bindings, enabled with the `python` feature.
+//!
+//! The module is built into a wheel by the enigma-python crate, e.g. `maturin develop` in
+//! `python/`. Letters are passed as strings, and whitespace in them is ignored:
+//!
+//! ```python
+//! import enigma
+//!
+//! machine = enigma.Enigma(rotors="I II III", reflector="B", ring_settings="AAA",
+//!                         plugboard="AM FI NV", window_positions="AAA")
+//! machine.message("Hello, world")
+//!
+//! machine = enigma.Enigma.from_key("B II IV V / 02 21 12 / AV BS CG DL FU / BLA")
+//! enigma.index_of_coincidence(machine.message(ciphertext))
+//! ```
+//!
+//! Invalid settings raise a `ValueError`.
+
+// The bindings generated by PyO3 use unsafe code internally.
+#![allow(unsafe_code)]
+
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+use alpha;
+use alpha::Alpha;
+use alpha::NonAlphabetic;
+use banburismus;
+use enigma;
+use entry_wheel::EntryWheel;
+use key;
+use model::MachineModel;
+use reflector;
+use rodding;
+use rotor::RotorFactory;
+use scoring;
+use tokens;
+use tokens::letter_vec;
+use wiring_recovery;
+
+/// A rotor's wiring and turnovers. `Rotor("EKMFLGDQVZNTOWYHXUSPAIBRCJ", "Q")` is rotor I.
+#[pyclass(module = "enigma")]
+#[derive(Debug, Copy, Clone)]
+pub struct Rotor {
+    factory: RotorFactory,
+    name: Option<&'static str>,
+}
+
+/// A reflector's wiring.
+#[pyclass(module = "enigma")]
+#[derive(Debug, Copy, Clone)]
+pub struct Reflector {
+    reflector: reflector::Reflector,
+    name: Option<&'static str>,
+}
+
+/// The rotors issued with the Enigma I, from the model catalogue.
+#[pyclass(module = "enigma")]
+#[derive(Debug, Copy, Clone)]
+pub struct Rotors {
+    model: MachineModel,
+}
+
+/// The reflectors issued with the M3, from the model catalogue.
+#[pyclass(module = "enigma")]
+#[derive(Debug, Copy, Clone)]
+pub struct Reflectors {
+    model: MachineModel,
+}
+
+impl Default for Rotors {
+    fn default() -> Self {
+        Rotors { model: MachineModel::EnigmaI }
+    }
+}
+
+impl Default for Reflectors {
+    fn default() -> Self {
+        Reflectors { model: MachineModel::M3 }
+    }
+}
+
+#[pyclass(module = "enigma")]
+#[derive(Debug, Copy, Clone)]
+pub struct Enigma {
+    enigma: enigma::Enigma,
+}
+
+/// Rotors can be given as a string of names, e.g. `"I II III"`, or as a list of `Rotor`s.
+#[derive(Debug, FromPyObject)]
+enum RotorsArg {
+    Names(String),
+    Rotors(Vec<Rotor>),
+}
+
+/// A reflector can be given by name, e.g. `"B"`, or as a `Reflector`.
+#[derive(Debug, FromPyObject)]
+enum ReflectorArg {
+    Name(String),
+    Reflector(Reflector),
+}
+
+#[pymethods]
+impl Rotor {
+    /// `wiring` is the 26 letters that `A-Z` are wired to, and `turnovers` the letters showing
+    /// in the window when the rotor turns over the rotor to its left.
+    #[new]
+    fn new(wiring: &str, turnovers: &str) -> PyResult<Rotor> {
+        let wiring = permutation(wiring).map_err(py)?;
+        let turnovers = letter_vec(turnovers).map_err(py)?;
+        Ok(Rotor {
+            factory: RotorFactory::new(&alpha::to_string(&wiring),
+                                       &alpha::to_string(&turnovers)),
+            name: None,
+        })
+    }
+
+    #[getter]
+    fn name(&self) -> Option<&'static str> {
+        self.name
+    }
+
+    #[getter]
+    fn wiring(&self) -> String {
+        let rotor = self.factory.with_ring_setting(Alpha::A);
+        (0..26).map(|c| char::from(Alpha::try_from_usize(rotor.sub(c)).unwrap())).collect()
+    }
+
+    #[getter]
+    fn turnovers(&self) -> String {
+        alpha::to_string(&self.factory.turnovers())
+    }
+
+    fn __repr__(&self) -> String {
+        match self.name {
+            Some(name) => format!("Rotor({:?})", name),
+            None => format!("Rotor({:?}, {:?})", self.wiring(), self.turnovers()),
+        }
+    }
+}
+
+#[pymethods]
+impl Reflector {
+    /// `wiring` is the 26 letters that `A-Z` are wired to, which must swap letters in pairs.
+    #[new]
+    fn new(wiring: &str) -> PyResult<Reflector> {
+        let wiring = permutation(wiring).map_err(py)?;
+        let swaps = wiring.iter()
+            .enumerate()
+            .all(|(i, &w)| usize::from(w) != i && usize::from(wiring[usize::from(w)]) == i);
+        if !swaps {
+            return Err(py("Reflector must swap letters in pairs"));
+        }
+        Ok(Reflector {
+            reflector: reflector::Reflector::from_wiring(&alpha::to_string(&wiring)),
+            name: None,
+        })
+    }
+
+    #[getter]
+    fn name(&self) -> Option<&'static str> {
+        self.name
+    }
+
+    #[getter]
+    fn wiring(&self) -> String {
+        (0..26)
+            .map(|c| char::from(Alpha::try_from_usize(self.reflector.sub(c)).unwrap()))
+            .collect()
+    }
+
+    fn __repr__(&self) -> String {
+        match self.name {
+            Some(name) => format!("Reflector({:?})", name),
+            None => format!("Reflector({:?})", self.wiring()),
+        }
+    }
+}
+
+#[pymethods]
+impl Rotors {
+    #[new]
+    fn new() -> Rotors {
+        Rotors::default()
+    }
+
+    /// Looks up a rotor by its Roman numeral, e.g. `"IV"`, returning `None` if there is no such
+    /// rotor.
+    fn get(&self, name: &str) -> Option<Rotor> {
+        let spec = self.model.rotors().iter().find(|w| w.name == name)?;
+        Some(Rotor {
+                 factory: spec.factory(),
+                 name: Some(spec.name),
+             })
+    }
+
+    fn names(&self) -> Vec<&'static str> {
+        self.model.rotors().iter().map(|w| w.name).collect()
+    }
+
+    fn __getitem__(&self, name: &str) -> PyResult<Rotor> {
+        self.get(name).ok_or_else(|| py("Unknown rotor"))
+    }
+
+    fn __len__(&self) -> usize {
+        self.model.rotors().len()
+    }
+}
+
+#[pymethods]
+impl Reflectors {
+    #[new]
+    fn new() -> Reflectors {
+        Reflectors::default()
+    }
+
+    /// Looks up a reflector by its letter, e.g. `"B"`, returning `None` if there is no such
+    /// reflector.
+    fn get(&self, name: &str) -> Option<Reflector> {
+        let spec = self.model.reflectors().iter().find(|r| r.name == name)?;
+        Some(Reflector {
+                 reflector: spec.reflector(),
+                 name: Some(spec.name),
+             })
+    }
+
+    fn names(&self) -> Vec<&'static str> {
+        self.model.reflectors().iter().map(|r| r.name).collect()
+    }
+
+    fn __getitem__(&self, name: &str) -> PyResult<Reflector> {
+        self.get(name).ok_or_else(|| py("Unknown reflector"))
+    }
+
+    fn __len__(&self) -> usize {
+        self.model.reflectors().len()
+    }
+}
+
+#[pymethods]
+impl Enigma {
+    /// Ring settings and window positions are all `A` if not given.
+    #[new]
+    #[pyo3(signature = (rotors, reflector, ring_settings=None, plugboard="",
+                        window_positions=None))]
+    fn new(rotors: RotorsArg,
+           reflector: ReflectorArg,
+           ring_settings: Option<&str>,
+           plugboard: &str,
+           window_positions: Option<&str>)
+           -> PyResult<Enigma> {
+        let rotors = match rotors {
+            RotorsArg::Names(names) => {
+                names.split_whitespace()
+                    .map(|name| Rotors::default().get(name).ok_or("Unknown rotor"))
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(py)?
+            }
+            RotorsArg::Rotors(rotors) => rotors,
+        };
+        let reflector = match reflector {
+            ReflectorArg::Name(name) => {
+                Reflectors::default().get(&name).ok_or_else(|| py("Unknown reflector"))?
+            }
+            ReflectorArg::Reflector(reflector) => reflector,
+        };
+        let rings = match ring_settings {
+            Some(rings) => letter_vec(rings).map_err(py)?,
+            None => vec![Alpha::A; rotors.len()],
+        };
+        if rings.len() != rotors.len() {
+            return Err(py("Must supply a ring setting for each rotor"));
+        }
+        let windows = match window_positions {
+            Some(windows) => letter_vec(windows).map_err(py)?,
+            None => vec![Alpha::A; rotors.len()],
+        };
+        let rotors: Vec<_> = rotors.iter()
+            .zip(rings)
+            .map(|(rotor, ring)| rotor.factory.with_ring_setting(ring))
+            .collect();
+        let enigma = enigma::EnigmaBuilder::default()
+            .rotors(rotors)
+            .reflector(reflector.reflector)
+            .plugboard(tokens::plugboard(plugboard).map_err(py)?)
+            .window_positions(windows)
+            .build()
+            .map_err(py)?;
+        Ok(Enigma { enigma })
+    }
+
+    /// Builds a machine from a key string, e.g. `"B II IV V / 02 21 12 / AV BS CG / BLA"`.
+    #[staticmethod]
+    fn from_key(key: &str) -> PyResult<Enigma> {
+        let builder = key::parse(key).map_err(|e| PyValueError::new_err(e.to_string()))?;
+        Ok(Enigma { enigma: builder.build().map_err(py)? })
+    }
+
+    /// Presses a single key, returning the letter which lights up.
+    fn press(&mut self, key: &str) -> PyResult<String> {
+        let letter: Alpha = key.parse().map_err(py)?;
+        Ok(self.enigma.press(letter).to_string())
+    }
+
+    /// Enciphers a message. Letters come out in upper case, and anything else is kept as it is
+    /// without pressing a key.
+    fn message(&mut self, text: &str) -> String {
+        self.enigma.encipher_str(text, NonAlphabetic::PassThrough).unwrap()
+    }
+
+    /// The letters showing in the windows, from left to right, e.g. `"ADU"`.
+    #[getter]
+    fn get_window_positions(&self) -> String {
+        alpha::to_string(&self.enigma.get_window_positions())
+    }
+
+    /// Turns the rotors by hand. This does not change the positions that `reset` returns to.
+    #[setter]
+    fn set_window_positions(&mut self, positions: &str) -> PyResult<()> {
+        let positions = letter_vec(positions).map_err(py)?;
+        if positions.len() != self.enigma.rotor_count() {
+            return Err(py("Must supply a window position for each rotor"));
+        }
+        self.enigma.set_window_positions(&positions);
+        Ok(())
+    }
+
+    fn reset(&mut self) {
+        self.enigma.reset()
+    }
+
+    /// The number of key presses before the machine's window positions start repeating, and the
+    /// number after which they repeat, as `(transient, period)`.
+    fn cycle(&self) -> (usize, usize) {
+        let cycle = self.enigma.cycle();
+        (cycle.transient, cycle.period)
+    }
+
+    fn __copy__(&self) -> Enigma {
+        *self
+    }
+
+    fn __repr__(&self) -> String {
+        format!("<Enigma at {}>", self.get_window_positions())
+    }
+}
+
+/// The number of positions at which two texts have the same letter.
+#[pyfunction]
+fn coincidences(a: &str, b: &str) -> PyResult<usize> {
+    Ok(scoring::coincidences(&letter_vec(a).map_err(py)?, &letter_vec(b).map_err(py)?))
+}
+
+/// The probability that two letters chosen at random from `text` are the same.
+#[pyfunction]
+fn index_of_coincidence(text: &str) -> PyResult<f64> {
+    Ok(scoring::index_of_coincidence(&letter_vec(text).map_err(py)?))
+}
+
+/// The weight of evidence, in decibans, of an observation with probability `if_true` when a
+/// hypothesis is true and `if_false` when it is false.
+#[pyfunction]
+fn decibans(if_true: f64, if_false: f64) -> f64 {
+    scoring::decibans(if_true, if_false)
+}
+
+/// The statistics used to weigh evidence in Banburismus.
+#[pyclass(module = "enigma", get_all, set_all)]
+#[derive(Debug, Copy, Clone)]
+pub struct Model {
+    depth_repeat_rate: f64,
+    random_repeat_rate: f64,
+    min_overlap: usize,
+}
+
+#[pymethods]
+impl Model {
+    #[new]
+    #[pyo3(signature = (depth_repeat_rate=None, random_repeat_rate=None, min_overlap=None))]
+    fn new(depth_repeat_rate: Option<f64>,
+           random_repeat_rate: Option<f64>,
+           min_overlap: Option<usize>)
+           -> Model {
+        let default = banburismus::Model::default();
+        Model {
+            depth_repeat_rate: depth_repeat_rate.unwrap_or(default.depth_repeat_rate),
+            random_repeat_rate: random_repeat_rate.unwrap_or(default.random_repeat_rate),
+            min_overlap: min_overlap.unwrap_or(default.min_overlap),
+        }
+    }
+}
+
+impl From<Model> for banburismus::Model {
+    fn from(model: Model) -> banburismus::Model {
+        banburismus::Model {
+            depth_repeat_rate: model.depth_repeat_rate,
+            random_repeat_rate: model.random_repeat_rate,
+            min_overlap: model.min_overlap,
+        }
+    }
+}
+
+/// Evidence from Banburismus, in decibans, for the distances between key letters.
+#[pyclass(module = "enigma")]
+#[derive(Debug, Clone)]
+pub struct Evidence {
+    evidence: banburismus::Evidence,
+}
+
+#[pymethods]
+impl Evidence {
+    /// The evidence that messages with indicator letter `to` started `distance` steps of the
+    /// wheel after messages with indicator letter `from`.
+    fn ahead(&self, from: &str, to: &str, distance: usize) -> PyResult<f64> {
+        Ok(self.evidence.ahead(letter(from)?, letter(to)?, distance))
+    }
+
+    /// The evidence that the key letter behind `to` is `distance` places along the alphabet
+    /// from the key letter behind `from`.
+    fn difference(&self, from: &str, to: &str, distance: usize) -> PyResult<f64> {
+        Ok(self.evidence.difference(letter(from)?, letter(to)?, distance))
+    }
+}
+
+/// Positions of indicator letters relative to each other on the alphabet.
+#[pyclass(module = "enigma")]
+#[derive(Debug, Copy, Clone)]
+pub struct Alignment {
+    alignment: banburismus::Alignment,
+}
+
+#[pymethods]
+impl Alignment {
+    #[getter]
+    fn score(&self) -> f64 {
+        self.alignment.score
+    }
+
+    /// The position of each indicator letter in the alignment, as `(letter, position)` pairs.
+    fn positions(&self) -> Vec<(String, String)> {
+        self.alignment
+            .letters()
+            .into_iter()
+            .map(|l| (l.to_string(), self.alignment.get(l).unwrap().to_string()))
+            .collect()
+    }
+
+    /// The same alignment, moved `shift` places along the alphabet.
+    fn shifted(&self, shift: usize) -> Alignment {
+        Alignment { alignment: self.alignment.shifted(shift) }
+    }
+
+    /// The shifts of this alignment which could be the true key letters behind each indicator
+    /// letter.
+    fn absolute(&self) -> Vec<Alignment> {
+        self.alignment.absolute().into_iter().map(|alignment| Alignment { alignment }).collect()
+    }
+
+    fn __repr__(&self) -> String {
+        let positions: Vec<_> =
+            self.positions().into_iter().map(|(l, p)| format!("{}={}", l, p)).collect();
+        format!("<Alignment {} score={:.1}>", positions.join(" "), self.alignment.score)
+    }
+}
+
+/// Collects Banburismus evidence for the right wheel from `(indicator, text)` pairs.
+#[pyfunction]
+#[pyo3(signature = (messages, model=None))]
+fn right_wheel_evidence(messages: Vec<(String, String)>, model: Option<Model>)
+                        -> PyResult<Evidence> {
+    let model = model.map(banburismus::Model::from).unwrap_or_default();
+    let messages = banburismus_messages(&messages).map_err(py)?;
+    Ok(Evidence { evidence: banburismus::right_wheel_evidence(&model, &messages) })
+}
+
+/// Collects Banburismus evidence for the middle wheel, given an absolute alignment of the right
+/// wheel and the right wheel's turnovers.
+#[pyfunction]
+#[pyo3(signature = (messages, right, right_turnovers, model=None))]
+fn middle_wheel_evidence(messages: Vec<(String, String)>,
+                         right: &Alignment,
+                         right_turnovers: &str,
+                         model: Option<Model>)
+                         -> PyResult<Evidence> {
+    let model = model.map(banburismus::Model::from).unwrap_or_default();
+    let messages = banburismus_messages(&messages).map_err(py)?;
+    let turnovers = letter_vec(right_turnovers).map_err(py)?;
+    Ok(Evidence {
+        evidence: banburismus::middle_wheel_evidence(&model,
+                                                     &messages,
+                                                     &right.alignment,
+                                                     &turnovers),
+    })
+}
+
+/// Chains evidence together into alignments of indicator letters, strongest first.
+#[pyfunction]
+fn chain(evidence: &Evidence, threshold: f64) -> Vec<Alignment> {
+    banburismus::chain(&evidence.evidence, threshold)
+        .into_iter()
+        .map(|alignment| Alignment { alignment })
+        .collect()
+}
+
+/// How well each candidate wheel's turnovers fit an absolute alignment, best first, as pairs of
+/// the candidate's index and a score in decibans.
+#[pyfunction]
+fn rank_wheels(evidence: &Evidence,
+               alignment: &Alignment,
+               candidates: Vec<Rotor>)
+               -> Vec<(usize, f64)> {
+    let candidates: Vec<_> = candidates.iter().map(|r| r.factory).collect();
+    banburismus::rank_wheels(&evidence.evidence, &alignment.alignment, &candidates)
+}
+
+/// A consistent guess at the right-hand rotor, found by `rod`.
+#[pyclass(module = "enigma", get_all)]
+#[derive(Debug, Clone)]
+pub struct RoddingCandidate {
+    /// The index of the rotor in the list of candidates.
+    rotor: usize,
+    /// The offset of the rotor's core before the first letter of the crib is pressed.
+    offset: String,
+    /// The letters of the crib before which the core changed, as the middle rotor stepped (and
+    /// perhaps the left rotor with it).
+    turnovers: Vec<usize>,
+    clicks: usize,
+    /// The pairs found in each core, one for each piece of the crib between the turnovers.
+    pairs: Vec<Vec<String>>,
+}
+
+#[pymethods]
+impl RoddingCandidate {
+    fn __repr__(&self) -> String {
+        format!("<RoddingCandidate rotor={} offset={} turnovers={:?} clicks={}>",
+                self.rotor,
+                self.offset,
+                self.turnovers,
+                self.clicks)
+    }
+}
+
+/// Rods a crib against every position of each candidate rotor, for machines without a
+/// plugboard. `entry_wheel` is the entry wheel's wiring, e.g. `"QWERTZUIOASDFGHJKPYXCVBNML"`
+/// for the Enigma K.
+#[pyfunction]
+#[pyo3(signature = (candidates, plaintext, ciphertext, entry_wheel=None))]
+fn rod(candidates: Vec<Rotor>,
+       plaintext: &str,
+       ciphertext: &str,
+       entry_wheel: Option<&str>)
+       -> PyResult<Vec<RoddingCandidate>> {
+    let candidates: Vec<_> = candidates.iter().map(|r| r.factory).collect();
+    let entry_wheel = match entry_wheel {
+        Some(wiring) => {
+            let wiring = permutation(wiring).map_err(py)?;
+            Some(EntryWheel::from_wiring(&alpha::to_string(&wiring)))
+        }
+        None => None,
+    };
+    let plaintext = letter_vec(plaintext).map_err(py)?;
+    let ciphertext = letter_vec(ciphertext).map_err(py)?;
+    let found = rodding::rod(&candidates, entry_wheel.as_ref(), &plaintext, &ciphertext);
+    Ok(found.into_iter()
+           .map(|c| {
+               RoddingCandidate {
+                   rotor: c.rotor,
+                   offset: c.offset.to_string(),
+                   turnovers: c.turnovers,
+                   clicks: c.clicks,
+                   pairs: c.pairs
+                       .iter()
+                       .map(|pairs| pairs.iter().map(|&(a, b)| format!("{}{}", a, b)).collect())
+                       .collect(),
+               }
+           })
+           .collect())
+}
+
+/// Recovers the wiring of an unknown rotor from key presses with known plaintext.
+///
+/// `rotors` lists the rotors from left to right, with `None` for the unknown one, and
+/// `ring_settings` has a letter for each of them. Each observation is `(window_positions,
+/// plaintext, ciphertext)`, e.g. `("ADU", "A", "B")`. Returns the wirings found, with `?` for
+/// wires the traffic did not pass through, and whether every consistent wiring is listed.
+#[pyfunction]
+#[pyo3(signature = (rotors, ring_settings, reflector, observations, plugboard="",
+                    limit=100))]
+fn recover_wiring(rotors: Vec<Option<Rotor>>,
+                  ring_settings: &str,
+                  reflector: ReflectorArg,
+                  observations: Vec<(String, String, String)>,
+                  plugboard: &str,
+                  limit: usize)
+                  -> PyResult<(Vec<String>, bool)> {
+    let (problem, observations) =
+        wiring_problem(&rotors, ring_settings, reflector, &observations, plugboard)?;
+    let solutions = problem.solve(&observations, limit).map_err(py)?;
+    let wirings = solutions.wirings.iter().map(|w| w.to_string()).collect();
+    Ok((wirings, solutions.exhaustive))
+}
+
+/// Works out the turnovers of an unknown rotor from consecutive key presses, taking the same
+/// arguments as `recover_wiring`. Returns the window positions known to be turnovers, and those
+/// the traffic says nothing about.
+#[pyfunction]
+#[pyo3(signature = (rotors, ring_settings, reflector, observations, plugboard=""))]
+fn recover_turnovers(rotors: Vec<Option<Rotor>>,
+                     ring_settings: &str,
+                     reflector: ReflectorArg,
+                     observations: Vec<(String, String, String)>,
+                     plugboard: &str)
+                     -> PyResult<(String, String)> {
+    let (problem, observations) =
+        wiring_problem(&rotors, ring_settings, reflector, &observations, plugboard)?;
+    let turnovers = problem.turnovers(&observations).map_err(py)?;
+    Ok((alpha::to_string(&turnovers.letters()), alpha::to_string(&turnovers.unknown())))
+}
+
+fn wiring_problem(rotors: &[Option<Rotor>],
+                  ring_settings: &str,
+                  reflector: ReflectorArg,
+                  observations: &[(String, String, String)],
+                  plugboard: &str)
+                  -> PyResult<(wiring_recovery::Problem, Vec<wiring_recovery::Observation>)> {
+    let reflector = match reflector {
+        ReflectorArg::Name(name) => {
+            Reflectors::default().get(&name).ok_or_else(|| py("Unknown reflector"))?
+        }
+        ReflectorArg::Reflector(reflector) => reflector,
+    };
+    let rings = letter_vec(ring_settings).map_err(py)?;
+    if rings.len() != rotors.len() {
+        return Err(py("Must supply a ring setting for each rotor"));
+    }
+    let unknown = rotors.iter()
+        .position(|r| r.is_none())
+        .ok_or_else(|| py("Must leave exactly one rotor unknown"))?;
+    let known = rotors.iter()
+        .zip(&rings)
+        .map(|(r, &ring)| r.map(|r| r.factory.with_ring_setting(ring)))
+        .collect();
+    let problem = wiring_recovery::Problem::new(known, rings[unknown], reflector.reflector)
+        .map_err(py)?
+        .plugboard(tokens::plugboard(plugboard).map_err(py)?);
+    let observations = observations.iter()
+        .map(|(windows, p, c)| {
+            Ok(wiring_recovery::Observation {
+                window_positions: letter_vec(windows).map_err(py)?,
+                plaintext: letter(p)?,
+                ciphertext: letter(c)?,
+            })
+        })
+        .collect::<PyResult<_>>()?;
+    Ok((problem, observations))
+}
+
+#[pymodule]
+#[pyo3(name = "enigma")]
+fn enigma_module(m: &Bound<PyModule>) -> PyResult<()> {
+    m.add_class::<Enigma>()?;
+    m.add_class::<Rotor>()?;
+    m.add_class::<Rotors>()?;
+    m.add_class::<Reflector>()?;
+    m.add_class::<Reflectors>()?;
+    m.add_function(wrap_pyfunction!(self::coincidences, m)?)?;
+    m.add_function(wrap_pyfunction!(self::index_of_coincidence, m)?)?;
+    m.add_function(wrap_pyfunction!(self::decibans, m)?)?;
+    m.add_class::<Model>()?;
+    m.add_class::<Evidence>()?;
+    m.add_class::<Alignment>()?;
+    m.add_function(wrap_pyfunction!(self::right_wheel_evidence, m)?)?;
+    m.add_function(wrap_pyfunction!(self::middle_wheel_evidence, m)?)?;
+    m.add_function(wrap_pyfunction!(self::chain, m)?)?;
+    m.add_function(wrap_pyfunction!(self::rank_wheels, m)?)?;
+    m.add_class::<RoddingCandidate>()?;
+    m.add_function(wrap_pyfunction!(self::rod, m)?)?;
+    m.add_function(wrap_pyfunction!(self::recover_wiring, m)?)?;
+    m.add_function(wrap_pyfunction!(self::recover_turnovers, m)?)?;
+    Ok(())
+}
+
+fn py(message: &str) -> PyErr {
+    PyValueError::new_err(message.to_string())
+}
+
+fn letter(text: &str) -> PyResult<Alpha> {
+    match letter_vec(text).map_err(py)?.as_slice() {
+        &[l] => Ok(l),
+        _ => Err(py("Must be a single letter")),
+    }
+}
+
+fn permutation(wiring: &str) -> Result<Vec<Alpha>, &'static str> {
+    let wiring = letter_vec(wiring)?;
+    let mut seen = [false; 26];
+    for &l in &wiring {
+        seen[usize::from(l)] = true;
+    }
+    if wiring.len() != 26 || !seen.iter().all(|&s| s) {
+        return Err("Wiring must contain each letter once");
+    }
+    Ok(wiring)
+}
+
+fn banburismus_messages(messages: &[(String, String)])
+                        -> Result<Vec<banburismus::Message>, &'static str> {
+    messages.iter()
+        .map(|(indicator, text)| {
+            let indicator = match letter_vec(indicator)?.as_slice() {
+                &[a, b, c] => [a, b, c],
+                _ => return Err("Indicators must be three letters"),
+            };
+            Ok(banburismus::Message {
+                indicator,
+                text: letter_vec(text)?,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_arguments() {
+        assert!(permutation("ABC").is_err());
+        assert!(permutation("AACDEFGHIJKLMNOPQRSTUVWXYZ").is_err());
+        assert!(banburismus_messages(&[("AB".to_string(), "XYZ".to_string())]).is_err());
+        let b = || ReflectorArg::Name("B".to_string());
+        assert!(wiring_problem(&[], "", b(), &[], "").is_err());
+        assert!(wiring_problem(&[None, None], "AA", b(), &[], "").is_err());
+    }
+
+    #[test]
+    fn catalogues() {
+        let rotors = Rotors::default();
+        let i = rotors.get("I").unwrap();
+        assert_eq!("EKMFLGDQVZNTOWYHXUSPAIBRCJ", i.wiring());
+        assert_eq!("Q", i.turnovers());
+        assert!(rotors.get("VI").is_none());
+        assert_eq!("YRUHQSLDPXNGOKMIEBFZCWVJAT", Reflectors::default().get("B").unwrap().wiring());
+    }
+}
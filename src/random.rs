@@ -0,0 +1,67 @@
+/// A small pseudo-random number generator (SplitMix64), so that searches and generated traffic
+/// can be repeated exactly from a seed.
+#[derive(Debug, Clone)]
+pub struct Rng {
+    state: u64,
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Rng {
+        Rng { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    /// A number from `0` up to, but not including, `n`.
+    ///
+    /// Panics if `n` is zero.
+    pub fn below(&mut self, n: usize) -> usize {
+        assert!(n > 0, "Cannot choose from nothing");
+        (self.next_u64() % n as u64) as usize
+    }
+
+    /// A number from `0.0` up to, but not including, `1.0`.
+    pub fn unit(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// True with probability `p`.
+    pub fn chance(&mut self, p: f64) -> bool {
+        self.unit() < p
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repeatable() {
+        let mut a = Rng::new(42);
+        let mut b = Rng::new(42);
+        for _ in 0..100 {
+            assert_eq!(a.next_u64(), b.next_u64());
+        }
+        assert_ne!(Rng::new(1).next_u64(), Rng::new(2).next_u64());
+    }
+
+    #[test]
+    fn ranges() {
+        let mut rng = Rng::new(7);
+        let mut seen = [false; 26];
+        for _ in 0..1000 {
+            seen[rng.below(26)] = true;
+            let u = rng.unit();
+            assert!((0.0..1.0).contains(&u));
+        }
+        assert!(seen.iter().all(|&s| s));
+        assert!(!rng.chance(0.0));
+        assert!(rng.chance(1.0));
+    }
+}
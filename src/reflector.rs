@@ -2,11 +2,13 @@ use cipher;
 use cipher::Cipher;
 use cipher::CipherChar;
 use cipher::is_pairs;
+use model::MachineModel;
+use state;
 
 /// A Reflector is a substitution cipher comprising 13 pairs.
 ///
 /// For example, an `AB` pair where `A -> B` and `B -> A`.
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
 pub struct Reflector {
     cipher: Cipher,
 }
@@ -15,12 +17,24 @@ pub struct Reflector {
 impl Reflector {
     pub fn new(cipher: Cipher) -> Reflector {
         assert!(is_pairs(cipher), "Reflector cipher must be in pairs");
-        Reflector { cipher: cipher }
+        Reflector { cipher }
+    }
+
+    /// Creates a reflector from the 26 letters that `A-Z` are wired to.
+    ///
+    /// Panics if the wiring is not made up of pairs.
+    pub fn from_wiring(wiring: &str) -> Reflector {
+        Reflector::new(cipher::from_string(wiring))
     }
 
     pub fn sub(&self, c: CipherChar) -> CipherChar {
         self.cipher[c]
     }
+
+    /// Folds the wiring into `hash`.
+    pub(crate) fn fingerprint(&self, hash: u64) -> u64 {
+        state::fingerprint(hash, self.cipher.iter().cloned())
+    }
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -38,6 +52,13 @@ impl Default for Reflectors {
     }
 }
 
+impl Reflectors {
+    /// Looks up a reflector by its letter, `"B"` or `"C"`.
+    pub fn get(&self, name: &str) -> Option<Reflector> {
+        MachineModel::M3.reflector(name)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -58,4 +79,12 @@ mod tests {
         let cipher = Cipher::from_string("RYUHQSLDPXNGOKMIEBFZCWVJAT");
         Reflector::new(cipher);
     }
+
+    #[test]
+    fn catalogue_matches_model() {
+        let reflectors = Reflectors::default();
+        assert_eq!(Some(reflectors.b), reflectors.get("B"));
+        assert_eq!(Some(reflectors.c), reflectors.get("C"));
+        assert_eq!(None, reflectors.get("A"));
+    }
 }
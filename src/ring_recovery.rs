@@ -0,0 +1,157 @@
+//! Recovering the ring settings once the bombe has found the wheel order, the plugboard and the
+//! core position.
+//!
+//! The bombe works with the rotor cores alone, so it gives the window positions as if every ring
+//! were set to `A`. Moving a rotor's ring on by one place and turning it on by one more place in
+//! the window leaves its core where it was, so every ring setting has a start position which
+//! deciphers the first letters in the same way. What the ring setting changes is when the rotor
+//! turns over the rotor to its left. The right rotor's ring decides when the middle rotor steps,
+//! and the middle rotor's ring when the left rotor steps. The left rotor's ring makes no
+//! difference, so it is left at `A`.
+//!
+//! Each of the 676 ring settings of the middle and right rotors is tried, with the start
+//! positions compensated, and the deciphered text is scored with an n-gram model. The text only
+//! reads as the language throughout once the turnovers fall in the right places. Ring settings
+//! whose turnovers fall outside the message give the same text, and so score the same.
+
+use alloc::vec::Vec;
+
+use alpha::Alpha;
+use enigma::Enigma;
+use enigma::EnigmaBuilder;
+use plugboard::Plugboard;
+use reflector::Reflector;
+use rotor::Rotor;
+use rotor::RotorFactory;
+use scoring::NgramModel;
+
+/// Everything the bombe finds: the whole key except the ring settings.
+#[derive(Debug, Clone)]
+pub struct PartialKey {
+    /// Every rotor from left to right. All of them step, as in the Enigma I and M3.
+    pub rotors: Vec<RotorFactory>,
+    pub reflector: Reflector,
+    pub plugboard: Plugboard,
+    /// The window positions at the start of the message, taking every ring setting to be `A`.
+    pub core_position: Vec<Alpha>,
+}
+
+/// A choice of ring settings, with the text it deciphers to.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Candidate {
+    pub ring_settings: Vec<Alpha>,
+    /// The window positions at the start of the message, compensated for the ring settings.
+    pub window_positions: Vec<Alpha>,
+    pub plaintext: Vec<Alpha>,
+    pub score: f64,
+}
+
+impl PartialKey {
+    /// The machine with `ring_settings`, turned so that its cores are at the core position.
+    pub fn machine(&self, ring_settings: &[Alpha]) -> Result<Enigma, &'static str> {
+        if self.core_position.len() != self.rotors.len() {
+            return Err("Must supply a window position for each rotor");
+        }
+        if ring_settings.len() != self.rotors.len() {
+            return Err("Must supply a ring setting for each rotor");
+        }
+        let rotors: Vec<Rotor> = self.rotors
+            .iter()
+            .zip(ring_settings)
+            .map(|(f, &ring)| f.with_ring_setting(ring))
+            .collect();
+        let window_positions: Vec<Alpha> = self.core_position
+            .iter()
+            .zip(ring_settings)
+            .map(|(&core, &ring)| core + ring)
+            .collect();
+        EnigmaBuilder::default()
+            .rotors(rotors)
+            .reflector(self.reflector)
+            .plugboard(self.plugboard)
+            .window_positions(window_positions)
+            .build()
+    }
+}
+
+/// Tries every ring setting of the middle and right rotors on `ciphertext`, returning the best
+/// `limit` candidates, best first.
+///
+/// Candidates which score the same are kept in order of ring setting.
+pub fn recover(key: &PartialKey,
+               ciphertext: &[Alpha],
+               model: &NgramModel,
+               limit: usize)
+               -> Result<Vec<Candidate>, &'static str> {
+    let count = key.rotors.len();
+    if count < 2 {
+        return Err("Must have at least two rotors");
+    }
+    let mut candidates = Vec::new();
+    for middle in Alpha::all() {
+        for right in Alpha::all() {
+            let mut ring_settings = vec![Alpha::A; count];
+            ring_settings[count - 2] = middle;
+            ring_settings[count - 1] = right;
+            let mut enigma = key.machine(&ring_settings)?;
+            let window_positions = enigma.get_window_positions();
+            let plaintext = enigma.message(ciphertext);
+            candidates.push(Candidate {
+                                score: model.score(&plaintext),
+                                ring_settings,
+                                window_positions,
+                                plaintext,
+                            });
+        }
+    }
+    candidates.sort_by(|a, b| b.score.total_cmp(&a.score));
+    candidates.truncate(limit);
+    Ok(candidates)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alpha::Alpha::*;
+    use reflector::Reflectors;
+    use rotor::Rotors;
+
+    fn key(core_position: Vec<Alpha>) -> PartialKey {
+        let rotors = Rotors::default();
+        PartialKey {
+            rotors: vec![rotors.ii, rotors.iv, rotors.v],
+            reflector: Reflectors::default().b,
+            plugboard: Plugboard::new(&[(A, V), (B, S), (C, G), (D, L), (F, U)]).unwrap(),
+            core_position,
+        }
+    }
+
+    #[test]
+    fn compensated_machines_agree_until_a_turnover() {
+        let key = key(vec![Q, B, C]);
+        let mut plain = key.machine(&[A, A, A]).unwrap();
+        let mut ringed = key.machine(&[A, D, K]).unwrap();
+        assert_eq!(vec![Q, E, M], ringed.get_window_positions());
+        // Rotor V turns over at Z, so with its ring at A the middle rotor steps on the 24th key;
+        // with its ring at K it steps on the 14th.
+        assert_eq!(plain.message(&[A; 13]), ringed.message(&[A; 13]));
+        assert_ne!(plain.message(&[A; 13]), ringed.message(&[A; 13]));
+    }
+
+    #[test]
+    fn recovers_ring_settings() {
+        let plaintext = Alpha::from_string("DIEDIVISIONWIRDMORGENFRUEHUMSECHSUHRMITZWEIREGIMENTERN\
+                                            ANGREIFENUNDDENUEBERGANGUEBERDENFLUSSERZWINGEN");
+        // The key has rings A D K, and starts at windows Q E M. The bombe gives the core position
+        // Q B C.
+        let key = key(vec![Q, B, C]);
+        let ciphertext = key.machine(&[A, D, K]).unwrap().message(&plaintext);
+
+        let candidates = recover(&key, &ciphertext, &NgramModel::german(), 5).unwrap();
+        let best = &candidates[0];
+        assert_eq!(plaintext, best.plaintext);
+        // The middle rotor never reaches its turnover, so its ring cannot be told apart.
+        assert_eq!(K, best.ring_settings[2]);
+        assert!(candidates.iter().all(|c| c.score <= best.score));
+    }
+}
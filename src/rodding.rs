@@ -0,0 +1,300 @@
+//! Rodding: Dilly Knox's attack on machines without a plugboard, such as the Enigma K.
+//!
+//! Without a plugboard, a key only passes through the entry wheel before reaching the right-hand
+//! rotor. While the middle rotor stays still, the middle rotor, left rotor and reflector together
+//! form a fixed 'core', which swaps letters in pairs. Given a crib, each guess at the right-hand
+//! rotor and its position sends the plain and cipher letters through the rotor's wiring into the
+//! core, where they must pair up consistently.
+//!
+//! The rotor's wiring at each position is read off a rod square, one rod per contact. A guess is
+//! ruled out by a contradiction, where a contact of the core would need to be paired with two
+//! different contacts. A 'click', where the same pair turns up again, is evidence for the guess.
+//!
+//! The ring setting is unknown, so positions are given as the offset of the rotor's core (the
+//! window position less the ring setting).
+//!
+//! When the middle rotor steps, the core changes, and the rest of the crib must pair up in a new
+//! core. Where that happens depends on the window position of the right-hand rotor, and is read
+//! off two more tables built from the rotor's turnovers. A `Lobster` gives the letters at which
+//! the middle rotor steps. A `Beetle` adds the letter after one of them, for when the middle
+//! rotor has stepped onto its own turnover and double steps, carrying the left rotor with it.
+
+use alloc::vec::Vec;
+
+use alpha::Alpha;
+use cipher::CipherChar;
+use entry_wheel::EntryWheel;
+use rotor::RotorFactory;
+
+/// The wiring of a rotor at each of its 26 offsets.
+#[derive(Debug, Copy, Clone)]
+pub struct RodSquare {
+    rods: [[CipherChar; 26]; 26],
+}
+
+impl RodSquare {
+    pub fn new(factory: &RotorFactory) -> RodSquare {
+        let rotor = factory.with_ring_setting(Alpha::A);
+        let mut rods = [[0; 26]; 26];
+        for (offset, rod) in rods.iter_mut().enumerate() {
+            for (letter, out) in rod.iter_mut().enumerate() {
+                *out = (rotor.sub((letter + offset) % 26) + 26 - offset) % 26;
+            }
+        }
+        RodSquare { rods }
+    }
+
+    /// The contact of the core that `letter` reaches with the rotor's core at `offset`.
+    pub fn get(&self, offset: Alpha, letter: Alpha) -> Alpha {
+        Alpha::try_from_usize(self.rods[usize::from(offset)][usize::from(letter)]).unwrap()
+    }
+}
+
+/// The letters of a crib at which the middle rotor steps, for each window position that the
+/// right-hand rotor starts at.
+///
+/// A step before the first letter is left out, as it only changes which core the crib starts in.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Lobster {
+    steps: Vec<Vec<usize>>,
+}
+
+impl Lobster {
+    /// The lobster for a crib of `length` letters.
+    pub fn new(factory: &RotorFactory, length: usize) -> Lobster {
+        let turnovers = factory.turnovers();
+        let steps = Alpha::all()
+            .map(|window| middle_steps(&turnovers, window, length).filter(|&i| i > 0).collect())
+            .collect();
+        Lobster { steps }
+    }
+
+    pub fn get(&self, window: Alpha) -> &[usize] {
+        &self.steps[usize::from(window)]
+    }
+}
+
+/// The letters of a crib at which the core changes if the left rotor steps during it, for each
+/// window position that the right-hand rotor starts at.
+///
+/// The left rotor steps on the letter after the middle rotor steps onto its own turnover, so there
+/// is one set of letters for each step of the middle rotor which leaves a letter after it. Each set
+/// holds the steps from the `Lobster`, along with that letter.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Beetle {
+    changes: Vec<Vec<Vec<usize>>>,
+}
+
+impl Beetle {
+    /// The beetle for a crib of `length` letters.
+    pub fn new(factory: &RotorFactory, length: usize) -> Beetle {
+        let turnovers = factory.turnovers();
+        let changes = Alpha::all()
+            .map(|window| {
+                let steps: Vec<usize> = middle_steps(&turnovers, window, length).collect();
+                steps.iter()
+                    .filter(|&&step| step + 1 < length)
+                    .map(|&step| {
+                        let mut changes: Vec<usize> =
+                            steps.iter().cloned().filter(|&i| i > 0).collect();
+                        changes.push(step + 1);
+                        changes.sort();
+                        changes.dedup();
+                        changes
+                    })
+                    .collect()
+            })
+            .collect();
+        Beetle { changes }
+    }
+
+    pub fn get(&self, window: Alpha) -> &[Vec<usize>] {
+        &self.changes[usize::from(window)]
+    }
+}
+
+/// The letters of a crib on which the middle rotor steps, with the right-hand rotor starting at
+/// `window`. It steps when the right-hand rotor leaves one of its `turnovers`.
+fn middle_steps<'a>(turnovers: &'a [Alpha],
+                    window: Alpha,
+                    length: usize)
+                    -> impl Iterator<Item = usize> + 'a {
+    (0..length).filter(move |&i| turnovers.contains(&(window + i)))
+}
+
+/// A guess at the right-hand rotor, and what the crib says about it.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Candidate {
+    /// The index of the rotor in the list of candidates.
+    pub rotor: usize,
+    /// The offset of the rotor's core before the first letter of the crib is pressed.
+    pub offset: Alpha,
+    /// The letters of the crib before which the core changed, as the middle rotor stepped (and
+    /// perhaps the left rotor with it).
+    pub turnovers: Vec<usize>,
+    pub contradictions: usize,
+    pub clicks: usize,
+    /// The pairs found in each core, one for each piece of the crib between the turnovers.
+    pub pairs: Vec<Vec<(Alpha, Alpha)>>,
+}
+
+impl Candidate {
+    pub fn is_consistent(&self) -> bool {
+        self.contradictions == 0
+    }
+}
+
+/// Tries a crib against every position of each candidate rotor, with the turnovers from its
+/// lobster and beetle, returning the consistent guesses.
+///
+/// A beetle only takes pairs away from a lobster's cores, so it is only tried when the lobster
+/// gives a contradiction. Guesses with the fewest turnovers come first, then those with the most
+/// clicks.
+pub fn rod(candidates: &[RotorFactory],
+           entry_wheel: Option<&EntryWheel>,
+           plaintext: &[Alpha],
+           ciphertext: &[Alpha])
+           -> Vec<Candidate> {
+    let mut found = Vec::new();
+    for (i, factory) in candidates.iter().enumerate() {
+        let rods = RodSquare::new(factory);
+        let lobster = Lobster::new(factory, plaintext.len());
+        let beetle = Beetle::new(factory, plaintext.len());
+        for offset in Alpha::all() {
+            // Windows with the same turnovers give the same guess, so each is only checked once.
+            let mut tried: Vec<(&[usize], bool)> = Vec::new();
+            let mut try_turnovers = |turnovers, found: &mut Vec<Candidate>| {
+                if let Some(&(_, consistent)) = tried.iter().find(|&&(t, _)| t == turnovers) {
+                    return consistent;
+                }
+                let candidate =
+                    check(&rods, i, offset, turnovers, entry_wheel, plaintext, ciphertext);
+                let consistent = candidate.is_consistent();
+                tried.push((turnovers, consistent));
+                if consistent {
+                    found.push(candidate);
+                }
+                consistent
+            };
+            for window in Alpha::all() {
+                if !try_turnovers(lobster.get(window), &mut found) {
+                    for turnovers in beetle.get(window) {
+                        try_turnovers(turnovers, &mut found);
+                    }
+                }
+            }
+        }
+    }
+    found.sort_by_key(|c| (c.turnovers.len(), usize::MAX - c.clicks));
+    found
+}
+
+/// Checks a crib against a single guess, with the core changing before each letter in
+/// `turnovers`.
+pub fn check(rods: &RodSquare,
+             rotor: usize,
+             offset: Alpha,
+             turnovers: &[usize],
+             entry_wheel: Option<&EntryWheel>,
+             plaintext: &[Alpha],
+             ciphertext: &[Alpha])
+             -> Candidate {
+    let contact = |l: Alpha| match entry_wheel {
+        Some(etw) => Alpha::try_from_usize(etw.sub(usize::from(l))).unwrap(),
+        None => l,
+    };
+
+    let mut candidate = Candidate {
+        rotor,
+        offset,
+        turnovers: turnovers.to_vec(),
+        contradictions: 0,
+        clicks: 0,
+        pairs: Vec::new(),
+    };
+    let mut core: [Option<Alpha>; 26] = [None; 26];
+    for (i, (&p, &c)) in plaintext.iter().zip(ciphertext).enumerate() {
+        if turnovers.contains(&i) {
+            candidate.pairs.push(pairs(&core));
+            core = [None; 26];
+        }
+        // The rotor steps before each letter is enciphered.
+        let o = offset + (i + 1);
+        let x = rods.get(o, contact(p));
+        let y = rods.get(o, contact(c));
+        match (core[usize::from(x)], core[usize::from(y)]) {
+            (None, None) => {
+                core[usize::from(x)] = Some(y);
+                core[usize::from(y)] = Some(x);
+            }
+            (Some(a), Some(b)) if a == y && b == x => candidate.clicks += 1,
+            _ => candidate.contradictions += 1,
+        }
+    }
+    candidate.pairs.push(pairs(&core));
+    candidate
+}
+
+fn pairs(core: &[Option<Alpha>; 26]) -> Vec<(Alpha, Alpha)> {
+    core.iter()
+        .enumerate()
+        .filter_map(|(i, p)| p.map(|p| (Alpha::try_from_usize(i).unwrap(), p)))
+        .filter(|&(a, b)| a < b)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alpha::Alpha::*;
+    use rotor::Rotors;
+
+    #[test]
+    fn rods_follow_wiring() {
+        let rods = RodSquare::new(&Rotors::default().i);
+
+        // Rotor I wires A to E, and B to K
+        assert_eq!(E, rods.get(A, A));
+        assert_eq!(J, rods.get(B, A));
+    }
+
+    #[test]
+    fn contradictions_and_clicks() {
+        let identity = RotorFactory::new("ABCDEFGHIJKLMNOPQRSTUVWXYZ", "Q");
+        let straight = RodSquare::new(&identity);
+
+        // Through straight wiring, A-B and B-C cannot both be core pairs.
+        let contradiction = check(&straight, 0, A, &[], None, &[A, B], &[B, C]);
+        assert_eq!(1, contradiction.contradictions);
+
+        let click = check(&straight, 0, A, &[], None, &[A, B], &[B, A]);
+        assert!(click.is_consistent());
+        assert_eq!(1, click.clicks);
+        assert_eq!(vec![vec![(A, B)]], click.pairs);
+
+        // ...unless the middle rotor steps in between.
+        let turnover = check(&straight, 0, A, &[1], None, &[A, B], &[B, C]);
+        assert!(turnover.is_consistent());
+        assert_eq!(vec![vec![(A, B)], vec![(B, C)]], turnover.pairs);
+    }
+
+    #[test]
+    fn lobsters_and_beetles() {
+        // Rotor I turns over the middle rotor as it leaves Q.
+        let rotor = Rotors::default().i;
+        let lobster = Lobster::new(&rotor, 30);
+        assert_eq!(&[16], lobster.get(A));
+        assert_eq!(&[26], lobster.get(Q));
+        assert!(Lobster::new(&rotor, 10).get(R).is_empty());
+
+        let beetle = Beetle::new(&rotor, 30);
+        assert_eq!(&[vec![16, 17]], beetle.get(A));
+        // Stepping on the first letter leaves the left rotor to step on the second.
+        assert_eq!(&[vec![1, 26], vec![26, 27]], beetle.get(Q));
+        assert!(Beetle::new(&rotor, 17).get(A).is_empty());
+
+        // Rotor VI has two turnovers.
+        let two = RotorFactory::new("JPGVOUMFYQBENHZRDKASXLICTW", "ZM");
+        assert_eq!(&[12, 25], Lobster::new(&two, 30).get(A));
+    }
+}
@@ -1,7 +1,12 @@
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
 use alpha::Alpha;
 use cipher;
 use cipher::Cipher;
 use cipher::CipherChar;
+use model::MachineModel;
+use state;
 
 /// A Rotor implements an arbitrary substitution cipher using wires connecting the 26 inputs to
 /// the 26 outputs.
@@ -17,7 +22,12 @@ use cipher::CipherChar;
 ///
 /// These two rotations affect the position of the internal wiring.
 ///
-/// There also is a notch set into the outer ring, which affects when the Rotors 'turnover'.
+/// There also are one or more notches set into the outer ring, which affect when the Rotors
+/// 'turnover'.
+///
+/// A Rotor can be inserted into the machine reversed, i.e. flipped over so that its right-hand
+/// contacts face left. Its wiring is then mirrored, and because its ring is read back to front it
+/// appears to step backwards through the alphabet.
 ///
 /// This Rotor implementation is immutable. It does not keep track of its own rotation within the
 /// Enigma machine (the window position).
@@ -27,53 +37,178 @@ pub struct Rotor {
     inverse_wiring: Cipher,
 
     ring_setting: CipherChar,
-    notch_position: CipherChar,
+    notches: Notches,
+
+    reversed: bool,
 }
 
+/// The positions on the ring which have a notch cut into them.
+type Notches = [bool; 26];
+
 impl Rotor {
+    #[cfg(test)]
     fn new(wiring: Cipher, notch_position: CipherChar, ring_setting: CipherChar) -> Rotor {
+        let mut notches = [false; 26];
+        notches[notch_position] = true;
+        Rotor::with_notches(wiring, notches, ring_setting)
+    }
+
+    fn with_notches(wiring: Cipher, notches: Notches, ring_setting: CipherChar) -> Rotor {
+        Rotor {
+            wiring,
+            inverse_wiring: Rotor::inverse_wiring(wiring),
+
+            notches,
+            ring_setting,
+
+            reversed: false,
+        }
+    }
+
+    /// The same Rotor, flipped over.
+    ///
+    /// Flipping mirrors each contact `x` to `-x`, and swaps the entry and exit faces, so the
+    /// wiring becomes `x -> -inverse_wiring(-x)`. The ring is mirrored in the same way, which
+    /// moves the ring setting and notches.
+    ///
+    /// Reversing twice gives the original Rotor.
+    pub fn reversed(&self) -> Rotor {
+        let mirror = |c: CipherChar| (26 - c) % 26;
+
+        let mut wiring = [0; 26];
+        let mut notches = [false; 26];
+        for i in 0..26 {
+            wiring[i] = mirror(self.inverse_wiring[mirror(i)]);
+            notches[i] = self.notches[mirror(i)];
+        }
         Rotor {
-            wiring: wiring,
+            wiring,
             inverse_wiring: Rotor::inverse_wiring(wiring),
 
-            notch_position: notch_position,
-            ring_setting: ring_setting,
+            notches,
+            ring_setting: mirror(self.ring_setting),
+
+            reversed: !self.reversed,
         }
     }
 
+    pub fn is_reversed(&self) -> bool {
+        self.reversed
+    }
+
+    /// Folds the wiring, notches and orientation into `hash`, but not the ring setting.
+    pub(crate) fn fingerprint(&self, hash: u64) -> u64 {
+        let notches = self.notches.iter().map(|&n| n as usize);
+        let wiring = self.wiring.iter().cloned().chain(notches).chain(Some(self.reversed as usize));
+        state::fingerprint(hash, wiring)
+    }
+
+    /// Whether this Rotor was made by `factory` and inserted the right way round, whatever its
+    /// ring setting.
+    pub fn is_from(&self, factory: &RotorFactory) -> bool {
+        !self.reversed && self.wiring == factory.wiring && self.notches == factory.notches
+    }
+
     pub fn sub(&self, c: CipherChar) -> CipherChar {
-        (self.wiring[(c + self.ring_setting) % 26] + 26 - self.ring_setting) % 26
+        (self.wiring[(c + 26 - self.ring_setting) % 26] + self.ring_setting) % 26
     }
 
     pub fn inverse_sub(&self, c: CipherChar) -> CipherChar {
-        (self.inverse_wiring[(c + self.ring_setting) % 26] + 26 - self.ring_setting) % 26
+        (self.inverse_wiring[(c + 26 - self.ring_setting) % 26] + self.ring_setting) % 26
+    }
+
+    pub fn ring_setting(&self) -> CipherChar {
+        self.ring_setting
+    }
+
+    /// How far the wiring core is turned from its starting contacts at `window_position`.
+    pub fn core_offset(&self, window_position: CipherChar) -> CipherChar {
+        core_offset(window_position, self.ring_setting)
     }
 
     pub fn notch_engaged(&self, window_position: CipherChar) -> bool {
-        (self.notch_position + 18) % 26 == window_position
+        self.notches[(window_position + 8) % 26]
     }
 
     fn inverse_wiring(wiring: Cipher) -> Cipher {
         let mut inverse = [0; 26];
-        for (i, v) in wiring.into_iter().enumerate() {
+        for (i, v) in wiring.iter().enumerate() {
             inverse[*v] = i;
         }
         inverse
     }
 }
 
+/// How far the wiring core of a rotor with `ring_setting` is turned at `window_position`, matching
+/// the substitution made by `Rotor::sub`.
+pub fn core_offset(window_position: CipherChar, ring_setting: CipherChar) -> CipherChar {
+    (window_position + 26 - ring_setting) % 26
+}
+
+/// Which way round a Rotor is inserted into the machine.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Orientation {
+    Forward,
+    Reversed,
+}
+
 #[derive(Debug, Copy, Clone)]
 pub struct RotorFactory {
     wiring: Cipher,
-    notch_position: CipherChar,
+    notches: Notches,
 }
 
 impl RotorFactory {
+    /// Creates a factory for a rotor with custom wiring.
+    ///
+    /// `wiring` is the 26 letters that `A-Z` are wired to, and `turnovers` is the letters which
+    /// show in the window when the rotor is about to turn over the rotor to its left. For
+    /// example, rotor I is `RotorFactory::new("EKMFLGDQVZNTOWYHXUSPAIBRCJ", "Q")`.
+    ///
+    /// Panics if the wiring is not a permutation of the alphabet, or if a turnover is not a
+    /// letter.
+    pub fn new(wiring: &str, turnovers: &str) -> RotorFactory {
+        let mut notches = [false; 26];
+        for c in turnovers.chars() {
+            notches[(cipher::from_char(c) + 8) % 26] = true;
+        }
+        RotorFactory {
+            wiring: cipher::from_string(wiring),
+            notches,
+        }
+    }
+
     pub fn with_ring_setting(&self, ring_setting: Alpha) -> Rotor {
-        Rotor::new(self.wiring, self.notch_position, usize::from(ring_setting))
+        Rotor::with_notches(self.wiring, self.notches, usize::from(ring_setting))
+    }
+
+    /// The window positions at which this rotor turns over the rotor to its left.
+    #[cfg(feature = "alloc")]
+    pub fn turnovers(&self) -> Vec<Alpha> {
+        Alpha::all().filter(|&c| self.notches[usize::from(c + 8)]).collect()
+    }
+
+    /// Like `with_ring_setting`, but the Rotor can be inserted reversed.
+    ///
+    /// The ring setting is the letter on the ring which is set against the core's `A` contact,
+    /// as read before the Rotor is flipped over.
+    pub fn with_orientation(&self, ring_setting: Alpha, orientation: Orientation) -> Rotor {
+        let rotor = self.with_ring_setting(ring_setting);
+        match orientation {
+            Orientation::Forward => rotor,
+            Orientation::Reversed => rotor.reversed(),
+        }
     }
 }
 
+fn notches(positions: &str) -> Notches {
+    let mut notches = [false; 26];
+    for c in positions.chars() {
+        notches[cipher::from_char(c)] = true;
+    }
+    notches
+}
+
 #[derive(Debug, Copy, Clone)]
 pub struct Rotors {
     pub i: RotorFactory,
@@ -88,28 +223,35 @@ impl Default for Rotors {
         Rotors {
             i: RotorFactory {
                 wiring: cipher::from_string("EKMFLGDQVZNTOWYHXUSPAIBRCJ"),
-                notch_position: cipher::from_char('Y'),
+                notches: notches("Y"),
             },
             ii: RotorFactory {
                 wiring: cipher::from_string("AJDKSIRUXBLHWTMCQGZNPYFVOE"),
-                notch_position: cipher::from_char('M'),
+                notches: notches("M"),
             },
             iii: RotorFactory {
                 wiring: cipher::from_string("BDFHJLCPRTXVZNYEIWGAKMUSQO"),
-                notch_position: cipher::from_char('D'),
+                notches: notches("D"),
             },
             iv: RotorFactory {
                 wiring: cipher::from_string("ESOVPZJAYQUIRHXLNFTGKDCMWB"),
-                notch_position: cipher::from_char('R'),
+                notches: notches("R"),
             },
             v: RotorFactory {
                 wiring: cipher::from_string("VZBRGITYUPSDNHLXAWMJQOFECK"),
-                notch_position: cipher::from_char('H'),
+                notches: notches("H"),
             },
         }
     }
 }
 
+impl Rotors {
+    /// Looks up a rotor by its Roman numeral, e.g. `"IV"`, among those issued with the Enigma I.
+    pub fn get(&self, name: &str) -> Option<RotorFactory> {
+        MachineModel::EnigmaI.rotor(name)
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -147,16 +289,84 @@ mod tests {
         assert_eq!(CC('Z'), rotor.inverse_sub(CC('Z')));
     }
 
+    #[test]
+    fn multiple_notches() {
+        let factory = RotorFactory::new("ABCDEFGHIJKLMNOPQRSTUVWXYZ", "AN");
+        let rotor = factory.with_ring_setting(Alpha::A);
+
+        assert!(rotor.notch_engaged(CC('A')));
+        assert!(rotor.notch_engaged(CC('N')));
+        assert!(!rotor.notch_engaged(CC('B')));
+    }
+
+    #[test]
+    #[cfg(feature = "alloc")]
+    fn turnovers() {
+        use alpha::Alpha::*;
+
+        assert_eq!(vec![Q], Rotors::default().i.turnovers());
+        assert_eq!(vec![A, N], RotorFactory::new("ABCDEFGHIJKLMNOPQRSTUVWXYZ", "NA").turnovers());
+    }
+
+    #[test]
+    fn reversed_rotor() {
+        let wiring = Cipher::from_string("BCDEFGHIJKLMNOPQRSTUVWXYZA");
+        let rotor = Rotor::new(wiring, CC('A'), CC('A')).reversed();
+
+        assert!(rotor.is_reversed());
+        assert_eq!(CC('B'), rotor.sub(CC('A')));
+        assert_eq!(CC('A'), rotor.sub(CC('Z')));
+        assert_eq!(CC('Z'), rotor.inverse_sub(CC('A')));
+    }
+
+    #[test]
+    fn reversed_orientation() {
+        let factory = Rotors::default().i;
+        let rotor = factory.with_orientation(Alpha::A, Orientation::Reversed);
+        let reversed_wiring = Cipher::from_string("GRMKNSJPIDTHOQYWZBFLVXAUCE");
+
+        for (c, &expected) in reversed_wiring.iter().enumerate() {
+            assert_eq!(expected, rotor.sub(c));
+            assert_eq!(c, rotor.inverse_sub(rotor.sub(c)));
+            assert_eq!(c == 20, rotor.notch_engaged(c));
+        }
+        assert!(!factory.with_orientation(Alpha::A, Orientation::Forward).is_reversed());
+    }
+
+    #[test]
+    fn reversed_twice() {
+        let rotor = Rotors::default().i.with_ring_setting(Alpha::C);
+        let twice = rotor.reversed().reversed();
+
+        assert!(!twice.is_reversed());
+        for c in 0..26 {
+            assert_eq!(rotor.sub(c), twice.sub(c));
+            assert_eq!(rotor.notch_engaged(c), twice.notch_engaged(c));
+        }
+    }
+
     #[test]
     fn a_b_flip_with_ring_position() {
         let wiring = Cipher::from_string("BACDEFGHIJKLMNOPQRSTUVWXYZ");
         let rotor = Rotor::new(wiring, CC('A'), CC('B'));
 
-        assert_eq!(CC('Z'), rotor.sub(CC('A')));
-        assert_eq!(CC('B'), rotor.sub(CC('B')));
-        assert_eq!(CC('A'), rotor.sub(CC('Z')));
-        assert_eq!(CC('Z'), rotor.inverse_sub(CC('A')));
-        assert_eq!(CC('B'), rotor.inverse_sub(CC('B')));
-        assert_eq!(CC('A'), rotor.inverse_sub(CC('Z')));
+        // The ring setting turns the wiring one place on, so the A-B flip becomes B-C.
+        assert_eq!(CC('A'), rotor.sub(CC('A')));
+        assert_eq!(CC('C'), rotor.sub(CC('B')));
+        assert_eq!(CC('B'), rotor.sub(CC('C')));
+        assert_eq!(CC('A'), rotor.inverse_sub(CC('A')));
+        assert_eq!(CC('C'), rotor.inverse_sub(CC('B')));
+        assert_eq!(CC('B'), rotor.inverse_sub(CC('C')));
+    }
+
+    #[test]
+    fn catalogue_matches_model() {
+        let rotors = Rotors::default();
+        let named = [("I", rotors.i), ("II", rotors.ii), ("III", rotors.iii), ("IV", rotors.iv),
+                     ("V", rotors.v)];
+        for &(name, factory) in &named {
+            assert!(factory.with_ring_setting(Alpha::A).is_from(&rotors.get(name).unwrap()));
+        }
+        assert!(rotors.get("VI").is_none());
     }
 }
@@ -0,0 +1,343 @@
+use core::fmt;
+
+use alpha::Alpha;
+use enigma::MAX_ROTORS;
+use plugboard::Plugboard;
+use tokens;
+
+/// Where a fingerprint starts: the FNV-1a offset basis.
+pub(crate) const FINGERPRINT: u64 = 0xcbf2_9ce4_8422_2325;
+
+/// Folds `values` into the FNV-1a hash `hash`. Fingerprints only need to tell settings apart, and
+/// must be the same on every platform, so that states can be written out and read back.
+pub(crate) fn fingerprint<I: IntoIterator<Item = usize>>(hash: u64, values: I) -> u64 {
+    values.into_iter().fold(hash, |hash, value| {
+        (hash ^ value as u64).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// A checkpoint of the parts of a machine which move as keys are pressed, taken by
+/// `Enigma::snapshot` and returned to with `Enigma::restore`.
+///
+/// A state also records the settings of the machine it was taken from: the ring settings, the
+/// plugboard, and fingerprints of the wheels and the reflector. `restore` refuses a state taken
+/// from a machine with other settings.
+///
+/// A state can be written out with `Display` and read back with `parse`, to resume a message
+/// later. It has five parts: the fingerprints of the wheels and of the reflector, the ring
+/// settings, the plugboard pairs, the window positions and the reflector position, for example
+/// `0b3c41e7a9f2d856 5e1d2c3b4a596877 / BUL / AV BS CG / BLA / A`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash)]
+pub struct MachineState {
+    window_positions: [Alpha; MAX_ROTORS],
+    ring_settings: [Alpha; MAX_ROTORS],
+    rotor_count: usize,
+    reflector_position: Alpha,
+    plugboard: Plugboard,
+    wheels: u64,
+    reflector: u64,
+}
+
+/// The settings a state was taken with, which do not change as keys are pressed.
+#[derive(Debug, Copy, Clone)]
+pub(crate) struct Settings<'a> {
+    pub ring_settings: &'a [Alpha],
+    pub plugboard: Plugboard,
+    /// The fingerprint of the wheels: their wiring, order and stepping, and the entry wheel.
+    pub wheels: u64,
+    pub reflector: u64,
+}
+
+/// One way in which two `MachineState`s differ, as found by `MachineState::diff`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Difference {
+    /// The machines hold different numbers of rotors.
+    RotorCount { from: usize, to: usize },
+    /// The machines have different wheels, or the same wheels in another order.
+    Wheels,
+    /// The rotor in `slot`, counting from the left, has a different ring setting.
+    RingSetting { slot: usize, from: Alpha, to: Alpha },
+    /// `letter` is plugged to a different letter, or to itself when unplugged.
+    Plug { letter: Alpha, from: Alpha, to: Alpha },
+    /// The machines have differently wired reflectors.
+    Reflector,
+    /// The rotor in `slot`, counting from the left, shows a different letter.
+    WindowPosition { slot: usize, from: Alpha, to: Alpha },
+    /// The reflector is turned to a different position.
+    ReflectorPosition { from: Alpha, to: Alpha },
+}
+
+impl MachineState {
+    pub(crate) fn new(window_positions: &[Alpha],
+                      reflector_position: Alpha,
+                      settings: Settings)
+                      -> MachineState {
+        let count = window_positions.len();
+        let mut state = MachineState {
+            window_positions: [Alpha::A; MAX_ROTORS],
+            ring_settings: [Alpha::A; MAX_ROTORS],
+            rotor_count: count,
+            reflector_position,
+            plugboard: settings.plugboard,
+            wheels: settings.wheels,
+            reflector: settings.reflector,
+        };
+        state.window_positions[..count].copy_from_slice(window_positions);
+        state.ring_settings[..count].copy_from_slice(settings.ring_settings);
+        state
+    }
+
+    /// Reads back a state written out with `Display`.
+    pub fn parse(text: &str) -> Result<MachineState, &'static str> {
+        let mut parts = text.split('/');
+        let (fingerprints, rings, plugs, windows, reflector) =
+            match (parts.next(), parts.next(), parts.next(), parts.next(), parts.next()) {
+                (Some(f), Some(r), Some(p), Some(w), Some(s)) if parts.next().is_none() => {
+                    (f, r, p, w, s)
+                }
+                _ => return Err("Must have five parts separated by '/'"),
+            };
+
+        let mut fingerprints = fingerprints.split_whitespace()
+            .map(|f| u64::from_str_radix(f, 16).map_err(|_| "Invalid fingerprint"));
+        let (wheels, reflector_wiring) =
+            match (fingerprints.next(), fingerprints.next(), fingerprints.next()) {
+                (Some(wheels), Some(reflector), None) => (wheels?, reflector?),
+                _ => return Err("Must have two fingerprints"),
+            };
+
+        let mut ring_settings = [Alpha::A; MAX_ROTORS];
+        let count = read_letters(rings, &mut ring_settings)?;
+        let mut window_positions = [Alpha::A; MAX_ROTORS];
+        if read_letters(windows, &mut window_positions)? != count {
+            return Err("Must have a window position for each ring setting");
+        }
+        let mut reflector_position = [Alpha::A];
+        if read_letters(reflector, &mut reflector_position)? != 1 {
+            return Err("Must have one reflector position");
+        }
+
+        let mut pairs = [(Alpha::A, Alpha::A); 13];
+        let mut plug_count = 0;
+        for (_, pair) in tokens::pairs(plugs) {
+            *pairs.get_mut(plug_count).ok_or("Too many plugboard pairs")? = pair?;
+            plug_count += 1;
+        }
+        let settings = Settings {
+            ring_settings: &ring_settings[..count],
+            plugboard: Plugboard::new(&pairs[..plug_count])?,
+            wheels,
+            reflector: reflector_wiring,
+        };
+        Ok(MachineState::new(&window_positions[..count], reflector_position[0], settings))
+    }
+
+    /// The letters showing in the windows, from left to right.
+    pub fn window_positions(&self) -> &[Alpha] {
+        &self.window_positions[..self.rotor_count]
+    }
+
+    pub fn reflector_position(&self) -> Alpha {
+        self.reflector_position
+    }
+
+    /// The ring settings, from left to right.
+    pub fn ring_settings(&self) -> &[Alpha] {
+        &self.ring_settings[..self.rotor_count]
+    }
+
+    pub fn plugboard(&self) -> &Plugboard {
+        &self.plugboard
+    }
+
+    /// Whether both states were taken from machines with the same settings, so that either can
+    /// be restored to the other's machine.
+    pub fn same_settings(&self, other: &MachineState) -> bool {
+        self.rotor_count == other.rotor_count && self.wheels == other.wheels &&
+        self.ring_settings() == other.ring_settings() &&
+        self.plugboard == other.plugboard && self.reflector == other.reflector
+    }
+
+    /// What would have to change to turn this state into `other`: first the settings, then the
+    /// positions.
+    ///
+    /// Ring settings and window positions are compared slot by slot, for as many rotors as both
+    /// states have.
+    pub fn diff<'a>(&'a self, other: &'a MachineState) -> impl Iterator<Item = Difference> + 'a {
+        let rotor_count = if self.rotor_count != other.rotor_count {
+            Some(Difference::RotorCount {
+                     from: self.rotor_count,
+                     to: other.rotor_count,
+                 })
+        } else {
+            None
+        };
+        let wheels = Some(Difference::Wheels).filter(|_| self.wheels != other.wheels);
+        let rings = self.ring_settings()
+            .iter()
+            .zip(other.ring_settings())
+            .enumerate()
+            .filter(|&(_, (from, to))| from != to)
+            .map(|(slot, (&from, &to))| Difference::RingSetting { slot, from, to });
+        let plugs = Alpha::all()
+            .map(move |letter| {
+                let plug = |p: &Plugboard| Alpha::A + p.sub(usize::from(letter));
+                (letter, plug(&self.plugboard), plug(&other.plugboard))
+            })
+            .filter(|&(_, from, to)| from != to)
+            .map(|(letter, from, to)| Difference::Plug { letter, from, to });
+        let reflector = Some(Difference::Reflector).filter(|_| self.reflector != other.reflector);
+        let windows = self.window_positions()
+            .iter()
+            .zip(other.window_positions())
+            .enumerate()
+            .filter(|&(_, (from, to))| from != to)
+            .map(|(slot, (&from, &to))| Difference::WindowPosition { slot, from, to });
+        let reflector_position = if self.reflector_position != other.reflector_position {
+            Some(Difference::ReflectorPosition {
+                     from: self.reflector_position,
+                     to: other.reflector_position,
+                 })
+        } else {
+            None
+        };
+        rotor_count.into_iter()
+            .chain(wheels)
+            .chain(rings)
+            .chain(plugs)
+            .chain(reflector)
+            .chain(windows)
+            .chain(reflector_position)
+    }
+}
+
+/// Reads letters into `letters`, returning how many there were.
+fn read_letters(text: &str, letters: &mut [Alpha]) -> Result<usize, &'static str> {
+    let mut count = 0;
+    for (_, letter) in tokens::letters(text) {
+        *letters.get_mut(count).ok_or("Too many letters")? = letter?;
+        count += 1;
+    }
+    Ok(count)
+}
+
+impl fmt::Display for MachineState {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:016x} {:016x} /", self.wheels, self.reflector)?;
+        write_letters(f, self.ring_settings())?;
+        f.write_str(" /")?;
+        for a in Alpha::all() {
+            let b = Alpha::A + self.plugboard.sub(usize::from(a));
+            if a < b {
+                write!(f, " {}{}", a, b)?;
+            }
+        }
+        f.write_str(" /")?;
+        write_letters(f, self.window_positions())?;
+        write!(f, " / {}", self.reflector_position)
+    }
+}
+
+fn write_letters(f: &mut fmt::Formatter, letters: &[Alpha]) -> fmt::Result {
+    if !letters.is_empty() {
+        f.write_str(" ")?;
+    }
+    letters.iter().try_for_each(|l| write!(f, "{}", l))
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Difference::RotorCount { from, to } => write!(f, "rotors: {} -> {}", from, to),
+            Difference::Wheels => write!(f, "wheels differ"),
+            Difference::RingSetting { slot, from, to } => {
+                write!(f, "ring {}: {} -> {}", slot, from, to)
+            }
+            Difference::Plug { letter, from, to } => {
+                write!(f, "plug {}: {} -> {}", letter, from, to)
+            }
+            Difference::Reflector => write!(f, "reflector wiring differs"),
+            Difference::WindowPosition { slot, from, to } => {
+                write!(f, "window {}: {} -> {}", slot, from, to)
+            }
+            Difference::ReflectorPosition { from, to } => {
+                write!(f, "reflector: {} -> {}", from, to)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alpha::Alpha::*;
+    use alloc::string::ToString;
+    use alloc::vec::Vec;
+
+    fn state(windows: &[Alpha], reflector_position: Alpha) -> MachineState {
+        let settings = Settings {
+            ring_settings: &[A, B, C, D][..windows.len()],
+            plugboard: Plugboard::new(&[(A, M), (F, I)]).unwrap(),
+            wheels: 1,
+            reflector: 2,
+        };
+        MachineState::new(windows, reflector_position, settings)
+    }
+
+    #[test]
+    fn diff() {
+        let before = state(&[A, D, U], A);
+        let after = state(&[B, E, U], C);
+        assert_eq!(0, before.diff(&before).count());
+
+        let differences: Vec<_> = before.diff(&after).map(|d| d.to_string()).collect();
+        assert_eq!(vec!["window 0: A -> B", "window 1: D -> E", "reflector: A -> C"],
+                   differences);
+    }
+
+    #[test]
+    fn diff_with_more_rotors() {
+        let three = state(&[A, A, A], A);
+        let four = state(&[A, A, A, Z], A);
+        let differences: Vec<_> = three.diff(&four).collect();
+        assert_eq!(vec![Difference::RotorCount { from: 3, to: 4 }], differences);
+        assert_ne!(three, four);
+        assert!(!three.same_settings(&four));
+    }
+
+    #[test]
+    fn diff_settings() {
+        let before = state(&[A, A, A], A);
+        let settings = Settings {
+            ring_settings: &[A, B, Z],
+            plugboard: Plugboard::new(&[(A, N), (F, I)]).unwrap(),
+            wheels: 3,
+            reflector: 4,
+        };
+        let after = MachineState::new(&[A, A, A], A, settings);
+        assert!(!before.same_settings(&after));
+
+        let differences: Vec<_> = before.diff(&after).map(|d| d.to_string()).collect();
+        assert_eq!(vec!["wheels differ",
+                        "ring 2: C -> Z",
+                        "plug A: M -> N",
+                        "plug M: A -> M",
+                        "plug N: N -> A",
+                        "reflector wiring differs"],
+                   differences);
+    }
+
+    #[test]
+    fn writes_and_parses() {
+        let state = state(&[B, L, A], C);
+        let text = state.to_string();
+        assert_eq!("0000000000000001 0000000000000002 / ABC / AM FI / BLA / C", text);
+        assert_eq!(Ok(state), MachineState::parse(&text));
+
+        assert!(MachineState::parse("1 2 / ABC / AM / BLA").is_err());
+        assert!(MachineState::parse("1 / ABC / AM / BLA / A").is_err());
+        assert!(MachineState::parse("1 2 / ABC / AM / BL / A").is_err());
+        assert!(MachineState::parse("1 2 / ABC / AM AB / BLA / A").is_err());
+        assert!(MachineState::parse("1 2 / ABC / / BLA / AB").is_err());
+    }
+}
@@ -0,0 +1,125 @@
+//! Reading letters, ring settings and plugboard pairs from text, for key strings and the bindings.
+//!
+//! Each reader yields every token along with what was read from it, so that key strings can point
+//! at a token which is wrong, and the bindings can simply report the message.
+
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+use alpha::Alpha;
+#[cfg(any(feature = "wasm", feature = "python"))]
+use plugboard::Plugboard;
+
+/// A token of the text being read, with the value read from it or why it could not be.
+pub(crate) type Token<'a, T> = (&'a str, Result<T, &'static str>);
+
+/// The letters of `text`, ignoring whitespace.
+pub(crate) fn letters<'a>(text: &'a str) -> impl Iterator<Item = Token<'a, Alpha>> {
+    characters(text).map(letter)
+}
+
+/// Ring settings, either as numbers from `01` to `26` separated by whitespace, or as letters.
+pub(crate) fn ring_settings<'a>(text: &'a str) -> impl Iterator<Item = Token<'a, Alpha>> {
+    let numbers = text.split_whitespace().all(|t| t.chars().all(|c| c.is_ascii_digit()));
+    let numbered = text.split_whitespace().filter(move |_| numbers).map(number);
+    let lettered = characters(text).filter(move |_| !numbers).map(letter);
+    numbered.chain(lettered)
+}
+
+/// Pairs of letters separated by whitespace, e.g. `AM FI NV`.
+pub(crate) fn pairs<'a>(text: &'a str) -> impl Iterator<Item = Token<'a, (Alpha, Alpha)>> {
+    text.split_whitespace().map(|pair| {
+        let mut letters = pair.chars().map(Alpha::try_from_char);
+        match (letters.next(), letters.next(), letters.next()) {
+            (Some(Ok(a)), Some(Ok(b)), None) => (pair, Ok((a, b))),
+            _ => (pair, Err("Plugboard pairs must be two letters")),
+        }
+    })
+}
+
+/// Every value read, or why the first bad token could not be read.
+#[cfg(feature = "alloc")]
+pub(crate) fn all<'a, T, I>(tokens: I) -> Result<Vec<T>, &'static str>
+    where I: Iterator<Item = Token<'a, T>>
+{
+    tokens.map(|(_, value)| value).collect()
+}
+
+/// All the letters of `text`, ignoring whitespace.
+#[cfg(feature = "alloc")]
+pub(crate) fn letter_vec(text: &str) -> Result<Vec<Alpha>, &'static str> {
+    all(letters(text))
+}
+
+/// A plugboard from pairs of letters separated by whitespace.
+#[cfg(any(feature = "wasm", feature = "python"))]
+pub(crate) fn plugboard(text: &str) -> Result<Plugboard, &'static str> {
+    Plugboard::new(&all(pairs(text))?)
+}
+
+/// Each character of `text` which is not whitespace, as a slice of it.
+fn characters(text: &str) -> impl Iterator<Item = &str> {
+    text.char_indices()
+        .filter(|&(_, c)| !c.is_whitespace())
+        .map(move |(at, c)| &text[at..at + c.len_utf8()])
+}
+
+fn letter(token: &str) -> Token<'_, Alpha> {
+    let letter = token.chars().next().and_then(|c| Alpha::try_from_char(c).ok());
+    (token, letter.ok_or("Must only contain letters"))
+}
+
+fn number(token: &str) -> Token<'_, Alpha> {
+    let number = match token.parse::<usize>() {
+        Ok(n) if (1..=26).contains(&n) => Ok(Alpha::A + (n - 1)),
+        Ok(_) => Err("Ring settings must be from 01 to 26"),
+        Err(_) => Err("Invalid ring setting"),
+    };
+    (token, number)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alpha::Alpha::*;
+
+    #[test]
+    fn reads_letters() {
+        let mut letters = letters("a M 1");
+        assert_eq!(Some(("a", Ok(A))), letters.next());
+        assert_eq!(Some(("M", Ok(M))), letters.next());
+        assert_eq!(Some(("1", Err("Must only contain letters"))), letters.next());
+        assert_eq!(None, letters.next());
+    }
+
+    #[test]
+    fn reads_ring_settings() {
+        let mut numbers = ring_settings("02 21 26");
+        assert_eq!([Some(("02", Ok(B))), Some(("21", Ok(U))), Some(("26", Ok(Z))), None],
+                   [numbers.next(), numbers.next(), numbers.next(), numbers.next()]);
+        let mut letters = ring_settings("B UZ");
+        assert_eq!([Some(("B", Ok(B))), Some(("U", Ok(U))), Some(("Z", Ok(Z))), None],
+                   [letters.next(), letters.next(), letters.next(), letters.next()]);
+        assert_eq!(Some(("27", Err("Ring settings must be from 01 to 26"))),
+                   ring_settings("01 27").nth(1));
+        assert_eq!(Some(("1", Err("Must only contain letters"))), ring_settings("A 1").nth(1));
+    }
+
+    #[test]
+    fn reads_pairs() {
+        let mut pairs = pairs("am FI ABC");
+        assert_eq!(Some(("am", Ok((A, M)))), pairs.next());
+        assert_eq!(Some(("FI", Ok((F, I)))), pairs.next());
+        assert_eq!(Some(("ABC", Err("Plugboard pairs must be two letters"))), pairs.next());
+        assert_eq!(None, pairs.next());
+    }
+
+    #[test]
+    #[cfg(any(feature = "wasm", feature = "python"))]
+    fn plugboards() {
+        assert_eq!(vec![(A, M), (F, I)], plugboard("am FI").unwrap().pairs());
+        assert!(plugboard("AB CDE").is_err());
+        assert!(plugboard("AB AC").is_err());
+        assert_eq!(Err("Must only contain letters"), letter_vec("A1"));
+    }
+}
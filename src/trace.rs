@@ -0,0 +1,101 @@
+use core::fmt;
+
+#[cfg(feature = "alloc")]
+use alloc::string::String;
+#[cfg(feature = "alloc")]
+use alloc::string::ToString;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+use alpha::Alpha;
+#[cfg(feature = "alloc")]
+use alpha::to_string;
+
+/// A part of the machine that the signal passes through.
+///
+/// Rotors are numbered by slot, from the left starting at 0. The entry wheel only appears for
+/// machines which have one that is not wired alphabetically.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Component {
+    PlugboardIn,
+    EntryWheelIn,
+    RotorForward(usize),
+    Reflector,
+    RotorBackward(usize),
+    EntryWheelOut,
+    PlugboardOut,
+}
+
+impl fmt::Display for Component {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Component::PlugboardIn => write!(f, "Plugboard in"),
+            Component::EntryWheelIn => write!(f, "Entry wheel in"),
+            Component::RotorForward(i) => write!(f, "Rotor {} forward", i + 1),
+            Component::Reflector => write!(f, "Reflector"),
+            Component::RotorBackward(i) => write!(f, "Rotor {} back", i + 1),
+            Component::EntryWheelOut => write!(f, "Entry wheel out"),
+            Component::PlugboardOut => write!(f, "Plugboard out"),
+        }
+    }
+}
+
+/// A single substitution along the signal path.
+///
+/// For rotors, `offset` is how far the wiring core is turned from its starting contacts, which
+/// depends on both the window position and the ring setting.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Hop {
+    pub component: Component,
+    pub offset: Option<Alpha>,
+    pub input: Alpha,
+    pub output: Alpha,
+}
+
+/// The path taken by the signal for a single key press, from key to lamp.
+///
+/// `Display` prints a table of the hops; `diagram` gives a more compact form.
+#[cfg(feature = "alloc")]
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Trace {
+    pub key: Alpha,
+    pub lamp: Alpha,
+    pub positions_before: Vec<Alpha>,
+    pub positions_after: Vec<Alpha>,
+    pub hops: Vec<Hop>,
+}
+
+#[cfg(feature = "alloc")]
+impl Trace {
+    /// The signal path on one line, e.g. `A > Plugboard in > A > Rotor 3 forward > C > ...`.
+    pub fn diagram(&self) -> String {
+        let mut s = char::from(self.key).to_string();
+        for hop in &self.hops {
+            s.push_str(&format!(" > {} > {}", hop.component, char::from(hop.output)));
+        }
+        s
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl fmt::Display for Trace {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f,
+                 "Key {} -> lamp {}, windows {} -> {}",
+                 char::from(self.key),
+                 char::from(self.lamp),
+                 to_string(&self.positions_before),
+                 to_string(&self.positions_after))?;
+        writeln!(f, "{:<18} {:>6} {:>3} {:>4}", "Component", "Offset", "In", "Out")?;
+        for hop in &self.hops {
+            let offset = hop.offset.map(char::from).unwrap_or('-');
+            writeln!(f,
+                     "{:<18} {:>6} {:>3} {:>4}",
+                     hop.component.to_string(),
+                     offset,
+                     char::from(hop.input),
+                     char::from(hop.output))?;
+        }
+        Ok(())
+    }
+}
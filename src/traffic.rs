@@ -0,0 +1,329 @@
+//! Generating traffic as operators sent it, mistakes included, for testing the attacks on
+//! imperfect data.
+//!
+//! Each message follows an indicator procedure. The day's key gives the wheel order, ring
+//! settings, plugboard and, before May 1940, a Grundstellung. The operator chooses a message key,
+//! enciphers it to give the indicator, then turns the rotors to the message key and enciphers the
+//! message:
+//!
+//! - `Procedure::Doubled`: the message key is enciphered twice over at the day's Grundstellung.
+//! - `Procedure::Single`: the operator also chooses the Grundstellung, sends it in the clear, and
+//!   enciphers the message key once at it.
+//!
+//! An `ErrorModel` gives the chance of each mistake, and every message records what really
+//! happened alongside what was sent.
+
+use alloc::vec::Vec;
+
+use alpha::Alpha;
+use enigma::Enigma;
+use random::Rng;
+
+/// How the message key is sent.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum Procedure {
+    Doubled,
+    Single,
+}
+
+/// The chance of each mistake. All are zero by default.
+#[derive(Debug, Copy, Clone, Default)]
+pub struct ErrorModel {
+    /// The chance, per message, that the operator turns one rotor a place too far or not far
+    /// enough when setting the message key.
+    pub wrong_positions: f64,
+    /// The chance, per message, that a doubled indicator is only enciphered once.
+    pub undoubled_indicator: f64,
+    /// The chance, per message, that the operator reuses the previous message key.
+    pub reused_key: f64,
+    /// The chance, per letter, that a letter is garbled into another in transmission.
+    pub garbled: f64,
+    /// The chance, per letter, that a letter is lost in transmission.
+    pub dropped: f64,
+}
+
+/// A mistake made with a message.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum OperatorError {
+    /// The message was enciphered from positions other than its message key.
+    WrongPositions,
+    /// The indicator was only enciphered once, though the procedure calls for it twice.
+    UndoubledIndicator,
+    /// The message key was the same as the previous message's.
+    ReusedKey,
+    /// The letter at `position` of the enciphered message was received as another.
+    Garbled { position: usize, was: Alpha },
+    /// The letter at `position` of the enciphered message was lost.
+    Dropped { position: usize, was: Alpha },
+}
+
+/// A message as it was received, with what really happened.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Message {
+    /// The Grundstellung sent in the clear, with `Procedure::Single`.
+    pub grundstellung: Option<Vec<Alpha>>,
+    pub indicator: Vec<Alpha>,
+    pub ciphertext: Vec<Alpha>,
+    pub truth: Truth,
+}
+
+/// What really happened with a message.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Truth {
+    pub message_key: Vec<Alpha>,
+    /// The window positions the message was enciphered from, which differ from the message key
+    /// if the operator set them wrongly.
+    pub start_positions: Vec<Alpha>,
+    pub plaintext: Vec<Alpha>,
+    /// The enciphered message before any transmission errors.
+    pub ciphertext: Vec<Alpha>,
+    pub errors: Vec<OperatorError>,
+}
+
+/// Enciphers messages on one day's key, as an operator would.
+#[derive(Debug, Clone)]
+pub struct Generator {
+    enigma: Enigma,
+    grundstellung: Vec<Alpha>,
+    procedure: Procedure,
+    errors: ErrorModel,
+    rng: Rng,
+    last_key: Option<Vec<Alpha>>,
+}
+
+impl Generator {
+    /// A generator for the day's key in `enigma`. With `Procedure::Doubled`, the machine's
+    /// window positions are the Grundstellung.
+    pub fn new(enigma: Enigma, procedure: Procedure, errors: ErrorModel, seed: u64) -> Generator {
+        Generator {
+            grundstellung: enigma.get_window_positions(),
+            enigma,
+            procedure,
+            errors,
+            rng: Rng::new(seed),
+            last_key: None,
+        }
+    }
+
+    /// Enciphers `plaintext` with a new message key.
+    pub fn message(&mut self, plaintext: &[Alpha]) -> Message {
+        let mut errors = Vec::new();
+        let count = self.grundstellung.len();
+
+        let message_key = match self.last_key {
+            Some(ref key) if self.rng.chance(self.errors.reused_key) => {
+                errors.push(OperatorError::ReusedKey);
+                key.clone()
+            }
+            _ => self.letters(count),
+        };
+        self.last_key = Some(message_key.clone());
+
+        let (grundstellung, doubled) = match self.procedure {
+            Procedure::Doubled => {
+                let undoubled = self.rng.chance(self.errors.undoubled_indicator);
+                if undoubled {
+                    errors.push(OperatorError::UndoubledIndicator);
+                }
+                (self.grundstellung.clone(), !undoubled)
+            }
+            Procedure::Single => (self.letters(count), false),
+        };
+        let mut enigma = self.enigma;
+        enigma.set_window_positions(&grundstellung);
+        let mut indicator = enigma.message(&message_key);
+        if doubled {
+            indicator.extend(enigma.message(&message_key));
+        }
+
+        let mut start_positions = message_key.clone();
+        if self.rng.chance(self.errors.wrong_positions) {
+            errors.push(OperatorError::WrongPositions);
+            let slot = self.rng.below(count);
+            start_positions[slot] = if self.rng.chance(0.5) {
+                start_positions[slot] + 1
+            } else {
+                start_positions[slot] - 1
+            };
+        }
+        enigma.set_window_positions(&start_positions);
+        let clean = enigma.message(plaintext);
+        let ciphertext = self.transmit(&clean, &mut errors);
+
+        Message {
+            grundstellung: match self.procedure {
+                Procedure::Doubled => None,
+                Procedure::Single => Some(grundstellung),
+            },
+            indicator,
+            ciphertext,
+            truth: Truth {
+                message_key,
+                start_positions,
+                plaintext: plaintext.to_vec(),
+                ciphertext: clean,
+                errors,
+            },
+        }
+    }
+
+    /// Sends `ciphertext`, garbling and dropping letters.
+    fn transmit(&mut self, ciphertext: &[Alpha], errors: &mut Vec<OperatorError>) -> Vec<Alpha> {
+        let mut received = Vec::with_capacity(ciphertext.len());
+        for (position, &was) in ciphertext.iter().enumerate() {
+            if self.rng.chance(self.errors.dropped) {
+                errors.push(OperatorError::Dropped { position, was });
+            } else if self.rng.chance(self.errors.garbled) {
+                errors.push(OperatorError::Garbled { position, was });
+                received.push(was + (1 + self.rng.below(25)));
+            } else {
+                received.push(was);
+            }
+        }
+        received
+    }
+
+    fn letters(&mut self, count: usize) -> Vec<Alpha> {
+        (0..count).map(|_| Alpha::A + self.rng.below(26)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use key;
+
+    const PLAINTEXT: &str = "KEINEBESONDERENEREIGNISSEXEIGENELAGEUNVERAENDERT";
+
+    fn generator(procedure: Procedure, errors: ErrorModel) -> Generator {
+        let key = "B I II III / 01 01 01 / AM FI NV PS TU WZ / FOL";
+        Generator::new(key::parse(key).unwrap().build().unwrap(), procedure, errors, 5)
+    }
+
+    /// Deciphers a message from the positions its indicator gives.
+    fn decipher(generator: &Generator, message: &Message) -> Vec<Alpha> {
+        let mut enigma = generator.enigma;
+        let grundstellung = message.grundstellung.as_ref().unwrap_or(&generator.grundstellung);
+        enigma.set_window_positions(grundstellung);
+        let message_key = enigma.message(&message.indicator[..3]);
+        enigma.set_window_positions(&message_key);
+        enigma.message(&message.ciphertext)
+    }
+
+    #[test]
+    fn doubled_indicator() {
+        let mut generator = generator(Procedure::Doubled, ErrorModel::default());
+        let plaintext = Alpha::from_string(PLAINTEXT);
+        let message = generator.message(&plaintext);
+        assert_eq!(6, message.indicator.len());
+        assert_eq!(None, message.grundstellung);
+        assert!(message.truth.errors.is_empty());
+        assert_eq!(message.truth.ciphertext, message.ciphertext);
+        assert_eq!(plaintext, decipher(&generator, &message));
+
+        // Both halves of the indicator decipher to the message key.
+        let mut enigma = generator.enigma;
+        let doubled = enigma.message(&message.indicator);
+        assert_eq!(message.truth.message_key, doubled[..3]);
+        assert_eq!(message.truth.message_key, doubled[3..]);
+        assert_ne!(message.indicator[..3], message.indicator[3..]);
+    }
+
+    #[test]
+    fn single_indicator() {
+        let mut generator = generator(Procedure::Single, ErrorModel::default());
+        let plaintext = Alpha::from_string(PLAINTEXT);
+        let first = generator.message(&plaintext);
+        let second = generator.message(&plaintext);
+        assert_eq!(3, first.indicator.len());
+        assert_ne!(first.grundstellung, second.grundstellung);
+        assert_ne!(first.truth.message_key, second.truth.message_key);
+        assert_eq!(plaintext, decipher(&generator, &first));
+        assert_eq!(plaintext, decipher(&generator, &second));
+    }
+
+    #[test]
+    fn operator_errors() {
+        let errors = ErrorModel {
+            wrong_positions: 1.0,
+            undoubled_indicator: 1.0,
+            reused_key: 1.0,
+            ..ErrorModel::default()
+        };
+        let mut generator = generator(Procedure::Doubled, errors);
+        let plaintext = Alpha::from_string(PLAINTEXT);
+        let first = generator.message(&plaintext);
+        let second = generator.message(&plaintext);
+
+        assert_eq!(vec![OperatorError::UndoubledIndicator, OperatorError::WrongPositions],
+                   first.truth.errors);
+        assert_eq!(3, first.indicator.len());
+        let off = first.truth
+            .message_key
+            .iter()
+            .zip(&first.truth.start_positions)
+            .filter(|&(k, p)| k != p)
+            .count();
+        assert_eq!(1, off);
+        assert_ne!(plaintext, decipher(&generator, &first));
+
+        assert_eq!(OperatorError::ReusedKey, second.truth.errors[0]);
+        assert_eq!(first.truth.message_key, second.truth.message_key);
+        assert_eq!(first.indicator, second.indicator);
+    }
+
+    #[test]
+    fn transmission_errors() {
+        let errors = ErrorModel {
+            garbled: 0.1,
+            dropped: 0.1,
+            ..ErrorModel::default()
+        };
+        let mut generator = generator(Procedure::Doubled, errors);
+        let message = generator.message(&Alpha::from_string(PLAINTEXT));
+        let truth = &message.truth;
+        let dropped = truth.errors
+            .iter()
+            .filter(|e| matches!(e, OperatorError::Dropped { .. }))
+            .count();
+        assert!(dropped > 0);
+        assert!(truth.errors.iter().any(|e| matches!(e, OperatorError::Garbled { .. })));
+        assert_eq!(truth.ciphertext.len() - dropped, message.ciphertext.len());
+
+        // Replaying the errors on the clean ciphertext gives what was received.
+        let mut received = Vec::new();
+        let mut errors = truth.errors.iter().peekable();
+        for (position, &letter) in truth.ciphertext.iter().enumerate() {
+            match errors.peek() {
+                Some(&&OperatorError::Dropped { position: p, was }) if p == position => {
+                    assert_eq!(letter, was);
+                    errors.next();
+                }
+                Some(&&OperatorError::Garbled { position: p, was }) if p == position => {
+                    assert_eq!(letter, was);
+                    assert_ne!(letter, message.ciphertext[received.len()]);
+                    received.push(message.ciphertext[received.len()]);
+                    errors.next();
+                }
+                _ => received.push(letter),
+            }
+        }
+        assert!(errors.next().is_none());
+        assert_eq!(message.ciphertext, received);
+    }
+
+    #[test]
+    fn repeatable() {
+        let errors = ErrorModel {
+            garbled: 0.05,
+            reused_key: 0.5,
+            ..ErrorModel::default()
+        };
+        let plaintext = Alpha::from_string(PLAINTEXT);
+        let mut a = generator(Procedure::Single, errors);
+        let mut b = generator(Procedure::Single, errors);
+        for _ in 0..5 {
+            assert_eq!(a.message(&plaintext), b.message(&plaintext));
+        }
+    }
+}
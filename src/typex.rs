@@ -0,0 +1,85 @@
+use alpha::Alpha;
+use enigma::Enigma;
+use enigma::EnigmaBuilder;
+use plugboard::Plugboard;
+use reflector::Reflector;
+use rotor::Rotor;
+
+/// Builds a Typex, the British counterpart of the Enigma.
+///
+/// The Typex has five rotors. From the left, there are three stepping rotors, then two stators
+/// which are set by hand but never step. The stepping works as on the Enigma, but Typex rotors
+/// usually have several notches each. Any of the rotors may be inserted reversed, using
+/// `Rotor::reversed`.
+///
+/// The Typex is built as an `Enigma` with stators, so it is used in the same way. Window positions
+/// are given for all five rotors, from left to right.
+///
+/// The wiring of the Typex rotors in service has never been published, so there is no catalogue
+/// of Typex rotors. Use `RotorFactory::new` and `Reflector::from_wiring` to create them.
+#[derive(Debug, Clone, Default)]
+pub struct TypexBuilder {
+    rotors: Option<[Rotor; 3]>,
+    stators: Option<[Rotor; 2]>,
+
+    window_positions: Option<[Alpha; 5]>,
+
+    reflector: Option<Reflector>,
+
+    plugboard: Option<Plugboard>,
+}
+
+impl TypexBuilder {
+    /// The stepping rotors, from left to right.
+    pub fn rotors(mut self, rotors: [Rotor; 3]) -> TypexBuilder {
+        self.rotors = Some(rotors);
+        self
+    }
+
+    /// The stators, from left to right.
+    pub fn stators(mut self, stators: [Rotor; 2]) -> TypexBuilder {
+        self.stators = Some(stators);
+        self
+    }
+
+    pub fn reflector(mut self, reflector: Reflector) -> TypexBuilder {
+        self.reflector = Some(reflector);
+        self
+    }
+
+    pub fn plugboard(mut self, plugboard: Plugboard) -> TypexBuilder {
+        self.plugboard = Some(plugboard);
+        self
+    }
+
+    pub fn window_positions(mut self, positions: [Alpha; 5]) -> TypexBuilder {
+        self.window_positions = Some(positions);
+        self
+    }
+
+    pub fn build(self) -> Result<Enigma, &'static str> {
+        let window_positions = match self.window_positions {
+            Some(p) => p,
+            None => return Err("Must supply initial rotor positions"),
+        };
+        let rotors = match self.rotors {
+            Some(r) => r,
+            None => return Err("Must supply stepping rotors"),
+        };
+        let stators = match self.stators {
+            Some(s) => s,
+            None => return Err("Must supply stators"),
+        };
+        let reflector = match self.reflector {
+            Some(r) => r,
+            None => return Err("Must supply reflector"),
+        };
+        EnigmaBuilder::default()
+            .rotors(rotors)
+            .stators(stators)
+            .window_positions(window_positions)
+            .reflector(reflector)
+            .plugboard(self.plugboard.unwrap_or_default())
+            .build()
+    }
+}
@@ -0,0 +1,340 @@
+//! Test vectors: messages with the keys they were sent on, for checking a machine against known
+//! traffic.
+//!
+//! A vector file holds one vector per paragraph, each a list of `field: value` lines. Lines
+//! starting with `#` are comments, and a line starting with whitespace continues the field
+//! above it, so long messages can be wrapped.
+//!
+//! ```text
+//! name: Operation Barbarossa, part 1
+//! model: Enigma I
+//! reflector: B
+//! rotors: II IV V
+//! rings: 02 21 12
+//! plugboard: AV BS CG DL FU HZ IN KM OW RX
+//! positions: BLA
+//! ciphertext: EDPUD NRGYS ZRCXN UYTPO MRMBO FKTBZ REZKM LXLVE FGUEY SIOZV EQMIK UBPMM YLKLT
+//! plaintext: AUFKL XABTE ILUNG XVONX KURTI NOWAX KURTI NOWAX NORDW ESTLX SEBEZ XSEBE ZXUAF
+//! ```
+//!
+//! `model`, `reflector`, `rotors`, `positions`, `ciphertext` and `plaintext` are required.
+//! `rotors` lists every wheel from left to right, including any fixed wheel such as the M4's
+//! Greek wheel. `rings` are numbers from `01` to `26` or letters, and default to `A`. A model
+//! with a settable reflector can also take a `reflector position`. `source` records where the
+//! vector came from. Spaces in the letters are ignored.
+
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+
+use alpha::Alpha;
+use enigma::Enigma;
+use enigma::EnigmaBuilder;
+use model::MachineModel;
+use plugboard::Plugboard;
+use rotor::Rotor;
+use tokens;
+
+/// A message and the key it was sent on.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct TestVector {
+    pub name: String,
+    /// The line of the vector file the vector starts on.
+    pub line: usize,
+    pub source: Option<String>,
+    pub model: MachineModel,
+    pub reflector: String,
+    pub reflector_position: Alpha,
+    /// Every rotor from left to right, including fixed wheels, by name.
+    pub rotors: Vec<String>,
+    pub ring_settings: Vec<Alpha>,
+    pub plugboard: Vec<(Alpha, Alpha)>,
+    pub window_positions: Vec<Alpha>,
+    pub ciphertext: Vec<Alpha>,
+    pub plaintext: Vec<Alpha>,
+}
+
+/// A problem in a vector file, with the line it was found on, counting from 1.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct VectorError {
+    pub line: usize,
+    pub message: &'static str,
+}
+
+/// A letter of the deciphered message which was not the expected plaintext letter.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Mismatch {
+    /// The letter's position in the message, counting from 0.
+    pub position: usize,
+    pub expected: Alpha,
+    pub actual: Alpha,
+}
+
+/// The result of deciphering one vector.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Report {
+    pub name: String,
+    pub mismatches: Vec<Mismatch>,
+    /// The difference in length between the ciphertext and the plaintext, if any.
+    pub length_difference: Option<(usize, usize)>,
+}
+
+impl fmt::Display for VectorError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+#[cfg(feature = "std")]
+impl ::std::error::Error for VectorError {}
+
+impl Report {
+    pub fn passed(&self) -> bool {
+        self.mismatches.is_empty() && self.length_difference.is_none()
+    }
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.passed() {
+            return write!(f, "{}: ok", self.name);
+        }
+        write!(f, "{}: {} mismatches", self.name, self.mismatches.len())?;
+        if let Some((ciphertext, plaintext)) = self.length_difference {
+            write!(f, ", {} letters of ciphertext for {} of plaintext", ciphertext, plaintext)?;
+        }
+        for m in &self.mismatches {
+            write!(f, "\n  {}: expected {}, got {}", m.position, m.expected, m.actual)?;
+        }
+        Ok(())
+    }
+}
+
+impl TestVector {
+    /// Builds the machine described by the vector's key, at its starting positions.
+    pub fn machine(&self) -> Result<Enigma, &'static str> {
+        let model = self.model;
+        if self.rotors.len() != model.slots() {
+            return Err("Wrong number of rotors for this model");
+        }
+        if self.ring_settings.len() != self.rotors.len() {
+            return Err("Must supply a ring setting for each rotor");
+        }
+        let mut rotors = Vec::new();
+        for (i, (name, &ring)) in self.rotors.iter().zip(&self.ring_settings).enumerate() {
+            let spec = if i < model.fixed_slots() {
+                model.fixed_rotors().iter().find(|w| w.name == name)
+            } else {
+                model.rotors().iter().find(|w| w.name == name)
+            };
+            rotors.push(spec.ok_or("Unknown rotor")?.factory().with_ring_setting(ring));
+        }
+        let (fixed, stepping): (&[Rotor], &[Rotor]) = rotors.split_at(model.fixed_slots());
+        EnigmaBuilder::for_model(model)
+            .fixed_rotors(fixed)
+            .rotors(stepping)
+            .reflector(model.reflector(&self.reflector).ok_or("Unknown reflector")?)
+            .reflector_position(self.reflector_position)
+            .plugboard(Plugboard::new(&self.plugboard)?)
+            .window_positions(&self.window_positions)
+            .build()
+    }
+
+    /// Deciphers the ciphertext and compares it with the plaintext, letter by letter.
+    pub fn run(&self) -> Result<Report, &'static str> {
+        let mut enigma = self.machine()?;
+        let deciphered = enigma.message(&self.ciphertext);
+        let mismatches = deciphered
+            .iter()
+            .zip(&self.plaintext)
+            .enumerate()
+            .filter(|&(_, (actual, expected))| actual != expected)
+            .map(|(position, (&actual, &expected))| {
+                     Mismatch {
+                         position,
+                         expected,
+                         actual,
+                     }
+                 })
+            .collect();
+        let length_difference = if self.ciphertext.len() != self.plaintext.len() {
+            Some((self.ciphertext.len(), self.plaintext.len()))
+        } else {
+            None
+        };
+        Ok(Report {
+               name: self.name.clone(),
+               mismatches,
+               length_difference,
+           })
+    }
+}
+
+/// Reads every vector in a vector file.
+pub fn parse(text: &str) -> Result<Vec<TestVector>, VectorError> {
+    let mut vectors = Vec::new();
+    let mut fields: Vec<(usize, &str, String)> = Vec::new();
+    for (i, line) in text.lines().enumerate().chain(Some((text.lines().count(), ""))) {
+        let line_number = i + 1;
+        if line.starts_with('#') {
+            continue;
+        }
+        if line.trim().is_empty() {
+            if !fields.is_empty() {
+                vectors.push(vector(&fields)?);
+                fields.clear();
+            }
+            continue;
+        }
+        if line.starts_with(char::is_whitespace) {
+            match fields.last_mut() {
+                Some(&mut (_, _, ref mut value)) => {
+                    value.push(' ');
+                    value.push_str(line.trim());
+                }
+                None => return Err(error(line_number, "Continuation line without a field")),
+            }
+            continue;
+        }
+        let colon = line.find(':').ok_or(error(line_number, "Expected 'field: value'"))?;
+        let (field, value) = (line[..colon].trim(), line[colon + 1..].trim());
+        if fields.iter().any(|&(_, f, _)| f == field) {
+            return Err(error(line_number, "Field given twice"));
+        }
+        fields.push((line_number, field, String::from(value)));
+    }
+    Ok(vectors)
+}
+
+/// Runs every vector in a vector file, reporting each in turn.
+pub fn run(text: &str) -> Result<Vec<Report>, VectorError> {
+    let mut reports = Vec::new();
+    for v in parse(text)? {
+        reports.push(v.run().map_err(|message| error(v.line, message))?);
+    }
+    Ok(reports)
+}
+
+fn error(line: usize, message: &'static str) -> VectorError {
+    VectorError { line, message }
+}
+
+/// Builds a vector from its fields, each with the line it was found on.
+fn vector(fields: &[(usize, &str, String)]) -> Result<TestVector, VectorError> {
+    let start = fields[0].0;
+    let get = |name: &str| fields.iter().find(|&&(_, f, _)| f == name);
+    let required = |name: &str| get(name).ok_or(error(start, "Missing a required field"));
+    for &(line, field, _) in fields {
+        if !FIELDS.contains(&field) {
+            return Err(error(line, "Unknown field"));
+        }
+    }
+
+    let &(line, _, ref model) = required("model")?;
+    let model = MachineModel::all()
+        .iter()
+        .cloned()
+        .find(|m| m.name() == model)
+        .ok_or(error(line, "Unknown model"))?;
+    let rotors: Vec<String> = required("rotors")?.2.split_whitespace().map(String::from).collect();
+    let ring_settings = match get("rings") {
+        Some(&(line, _, ref rings)) => {
+            tokens::all(tokens::ring_settings(rings)).map_err(|m| error(line, m))?
+        }
+        None => vec![Alpha::A; rotors.len()],
+    };
+    let plugboard = match get("plugboard") {
+        Some(&(line, _, ref plugs)) => {
+            tokens::all(tokens::pairs(plugs)).map_err(|m| error(line, m))?
+        }
+        None => Vec::new(),
+    };
+    let reflector_position = match get("reflector position") {
+        Some(&(line, _, ref p)) => {
+            p.parse().map_err(|_| error(line, "Invalid reflector position"))?
+        }
+        None => Alpha::A,
+    };
+    let letters_of = |name| -> Result<Vec<Alpha>, VectorError> {
+        let &(line, _, ref value) = required(name)?;
+        tokens::letter_vec(value).map_err(|m| error(line, m))
+    };
+
+    Ok(TestVector {
+           name: get("name").map_or_else(|| format!("line {}", start), |f| f.2.clone()),
+           line: start,
+           source: get("source").map(|f| f.2.clone()),
+           model,
+           reflector: required("reflector")?.2.clone(),
+           reflector_position,
+           rotors,
+           ring_settings,
+           plugboard,
+           window_positions: letters_of("positions")?,
+           ciphertext: letters_of("ciphertext")?,
+           plaintext: letters_of("plaintext")?,
+       })
+}
+
+const FIELDS: [&str; 11] = ["name", "source", "model", "reflector", "reflector position",
+                            "rotors", "rings", "plugboard", "positions", "ciphertext",
+                            "plaintext"];
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alpha::Alpha::*;
+    use alloc::string::ToString;
+
+    const VECTOR: &str = "
+# The first vector in the rotor tests.
+name: BDZGO
+model: Enigma M3
+reflector: B
+rotors: I II III
+positions: AAA
+ciphertext: AAAAA
+plaintext: BDZGO
+";
+
+    #[test]
+    fn parses_vector() {
+        let vectors = parse(VECTOR).unwrap();
+        assert_eq!(1, vectors.len());
+        let v = &vectors[0];
+        assert_eq!("BDZGO", v.name);
+        assert_eq!(3, v.line);
+        assert_eq!(vec![A, A, A], v.ring_settings);
+        assert!(v.plugboard.is_empty());
+        assert!(v.run().unwrap().passed());
+    }
+
+    #[test]
+    fn reports_mismatches_by_position() {
+        let text = VECTOR.replace("plaintext: BDZGO", "plaintext: BDXG\n    OQ");
+        let report = parse(&text).unwrap()[0].run().unwrap();
+        assert_eq!(vec![Mismatch { position: 2, expected: X, actual: Z }], report.mismatches);
+        assert_eq!(Some((5, 6)), report.length_difference);
+        assert_eq!("BDZGO: 1 mismatches, 5 letters of ciphertext for 6 of plaintext\n  \
+                    2: expected X, got Z",
+                   report.to_string());
+    }
+
+    #[test]
+    fn invalid_files() {
+        let invalid = |from, to| parse(&VECTOR.replace(from, to)).unwrap_err();
+        assert_eq!(error(4, "Unknown model"), invalid("Enigma M3", "Enigma M5"));
+        assert_eq!(error(3, "Missing a required field"), invalid("positions: AAA", ""));
+        assert_eq!(error(6, "Unknown field"), invalid("rotors:", "wheels:"));
+        assert_eq!(error(8, "Must only contain letters"), invalid("AAAAA", "AAAA1"));
+        assert_eq!(error(4, "Expected 'field: value'"), invalid("model:", "model"));
+        assert_eq!(error(1, "Continuation line without a field"), parse("  ABC").unwrap_err());
+        assert_eq!(error(7, "Ring settings must be from 01 to 26"),
+                   invalid("rotors: I II III", "rotors: I II III\nrings: 01 01 27"));
+        assert_eq!(error(7, "Plugboard pairs must be two letters"),
+                   invalid("rotors: I II III", "rotors: I II III\nplugboard: AB ABC"));
+
+        let v = &parse(&VECTOR.replace("III", "IX")).unwrap()[0];
+        assert_eq!(Err("Unknown rotor"), v.run());
+    }
+}
@@ -0,0 +1,171 @@
+//! JavaScript bindings, enabled with the `wasm` feature.
+//!
+//! Everything is passed as strings, e.g. in Node:
+//!
+//! ```js
+//! const { EnigmaBuilder } = require("./pkg/enigma_wasm.js");
+//! const enigma = new EnigmaBuilder()
+//!     .rotors("I II III")
+//!     .ringSettings("AAA")
+//!     .reflector("B")
+//!     .plugboard("AM FI NV")
+//!     .windowPositions("AAA")
+//!     .build();
+//! enigma.message("Hello, world");
+//! ```
+//!
+//! Invalid settings throw an `Error`.
+
+// The bindings generated by wasm-bindgen use unsafe code internally.
+#![allow(unsafe_code)]
+
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
+use wasm_bindgen::prelude::*;
+
+use alpha::Alpha;
+use alpha::NonAlphabetic;
+use enigma;
+use reflector::Reflector;
+use reflector::Reflectors;
+use rotor::RotorFactory;
+use rotor::Rotors;
+use tokens;
+
+/// Builds an `Enigma`. Rotors and ring settings can be given in either order.
+#[wasm_bindgen]
+#[derive(Debug, Clone, Default)]
+pub struct EnigmaBuilder {
+    builder: enigma::EnigmaBuilder,
+    rotors: Vec<RotorFactory>,
+    ring_settings: Option<Vec<Alpha>>,
+}
+
+#[wasm_bindgen]
+#[derive(Debug, Copy, Clone)]
+pub struct Enigma {
+    enigma: enigma::Enigma,
+}
+
+#[wasm_bindgen]
+impl EnigmaBuilder {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> EnigmaBuilder {
+        EnigmaBuilder::default()
+    }
+
+    /// The stepping rotors from left to right, by Roman numeral, e.g. `"I II III"`.
+    pub fn rotors(mut self, names: &str) -> Result<EnigmaBuilder, JsError> {
+        let rotors = names.split_whitespace().map(rotor).collect::<Result<_, _>>();
+        self.rotors = rotors.map_err(js)?;
+        Ok(self)
+    }
+
+    /// One letter for each rotor, e.g. `"AAA"`. All `A` if not given.
+    #[wasm_bindgen(js_name = ringSettings)]
+    pub fn ring_settings(mut self, letters: &str) -> Result<EnigmaBuilder, JsError> {
+        self.ring_settings = Some(tokens::letter_vec(letters).map_err(js)?);
+        Ok(self)
+    }
+
+    /// `"B"` or `"C"`.
+    pub fn reflector(mut self, name: &str) -> Result<EnigmaBuilder, JsError> {
+        self.builder = self.builder.reflector(reflector(name).map_err(js)?);
+        Ok(self)
+    }
+
+    /// Pairs of letters separated by spaces, e.g. `"AM FI NV"`.
+    pub fn plugboard(mut self, pairs: &str) -> Result<EnigmaBuilder, JsError> {
+        self.builder = self.builder.plugboard(tokens::plugboard(pairs).map_err(js)?);
+        Ok(self)
+    }
+
+    /// One letter for each rotor, e.g. `"AAA"`.
+    #[wasm_bindgen(js_name = windowPositions)]
+    pub fn window_positions(mut self, letters: &str) -> Result<EnigmaBuilder, JsError> {
+        self.builder = self.builder.window_positions(tokens::letter_vec(letters).map_err(js)?);
+        Ok(self)
+    }
+
+    pub fn build(self) -> Result<Enigma, JsError> {
+        Ok(Enigma { enigma: self.build_enigma().map_err(js)? })
+    }
+
+    fn build_enigma(self) -> Result<enigma::Enigma, &'static str> {
+        let count = self.rotors.len();
+        let rings = self.ring_settings.unwrap_or_else(|| vec![Alpha::A; count]);
+        if rings.len() != self.rotors.len() {
+            return Err("Must supply a ring setting for each rotor");
+        }
+        let rotors: Vec<_> = self.rotors
+            .iter()
+            .zip(rings)
+            .map(|(factory, ring)| factory.with_ring_setting(ring))
+            .collect();
+        self.builder.rotors(rotors).build()
+    }
+}
+
+#[wasm_bindgen]
+impl Enigma {
+    /// Presses a single key, returning the letter which lights up.
+    pub fn press(&mut self, key: &str) -> Result<String, JsError> {
+        let letter: Alpha = key.parse().map_err(js)?;
+        Ok(self.enigma.press(letter).to_string())
+    }
+
+    /// Enciphers a message. Letters come out in upper case, and anything else is kept as it is
+    /// without pressing a key.
+    pub fn message(&mut self, text: &str) -> String {
+        self.enigma.encipher_str(text, NonAlphabetic::PassThrough).unwrap()
+    }
+
+    /// The letters showing in the windows, from left to right, e.g. `"ADU"`.
+    #[wasm_bindgen(js_name = getWindowPositions)]
+    pub fn get_window_positions(&self) -> String {
+        self.enigma.get_window_positions().iter().map(|&l| char::from(l)).collect()
+    }
+
+    pub fn reset(&mut self) {
+        self.enigma.reset()
+    }
+}
+
+fn js(message: &str) -> JsError {
+    JsError::new(message)
+}
+
+fn rotor(name: &str) -> Result<RotorFactory, &'static str> {
+    Rotors::default().get(name).ok_or("Unknown rotor")
+}
+
+fn reflector(name: &str) -> Result<Reflector, &'static str> {
+    Reflectors::default().get(name).ok_or("Unknown reflector")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alpha::Alpha::*;
+
+    #[test]
+    fn builds_machine() {
+        let builder = EnigmaBuilder {
+            rotors: vec![rotor("I").unwrap(), rotor("II").unwrap(), rotor("III").unwrap()],
+            builder: enigma::EnigmaBuilder::default()
+                .reflector(reflector("B").unwrap())
+                .window_positions(vec![A, A, A]),
+            ring_settings: None,
+        };
+        let mut enigma = builder.build_enigma().unwrap();
+        assert_eq!(vec![B, D, Z, G, O], enigma.message(&[A, A, A, A, A]));
+    }
+
+    #[test]
+    fn invalid_settings() {
+        assert!(rotor("VI").is_err());
+        assert!(reflector("A").is_err());
+    }
+}
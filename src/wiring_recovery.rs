@@ -0,0 +1,422 @@
+//! Recovering the wiring of an unknown rotor from known plaintext.
+//!
+//! Everything else about the machine must be known: the other rotors, the reflector, the
+//! plugboard and the window positions at each letter. The unknown rotor's ring setting must be
+//! known too, although any ring setting gives an equivalent wiring, rotated.
+//!
+//! For each letter, the known parts to the right of the unknown rotor carry the plain and cipher
+//! letters to two of its contacts, `a` and `e`. The rotors to its left and the reflector form a
+//! core which swaps letters in pairs, so if `W` is the unknown wiring (adjusted for the core
+//! offset), then `W(e) = core(W(a))`. Each guess at one contact's wire forces others through these
+//! constraints, so a search over the guesses quickly finds every wiring consistent with the
+//! traffic, or shows that there are none.
+//!
+//! Turnovers are found separately, from consecutive key presses: the rotor to the left of the
+//! unknown rotor steps exactly when the unknown rotor's notch is engaged, unless it is stepping
+//! anyway because of its own notch.
+
+use core::fmt;
+
+use alloc::string::String;
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
+use alpha::Alpha;
+use cipher::CipherChar;
+use entry_wheel::EntryWheel;
+use plugboard::Plugboard;
+use reflector::Reflector;
+use rotor;
+use rotor::Rotor;
+use rotor::RotorFactory;
+
+/// A single key press, with the window positions of every rotor as the letter was enciphered.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Observation {
+    pub window_positions: Vec<Alpha>,
+    pub plaintext: Alpha,
+    pub ciphertext: Alpha,
+}
+
+/// A machine with one rotor of unknown wiring.
+///
+/// All rotors are taken to be stepping rotors, as in the Enigma I and M3.
+#[derive(Debug, Clone)]
+pub struct Problem {
+    rotors: Vec<Option<Rotor>>,
+    unknown: usize,
+    ring_setting: CipherChar,
+    reflector: Reflector,
+    plugboard: Plugboard,
+    entry_wheel: Option<EntryWheel>,
+}
+
+/// The wiring of the unknown rotor, as far as the traffic shows it.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Wiring {
+    wires: [Option<Alpha>; 26],
+}
+
+/// The wirings found by `Problem::solve`.
+#[derive(Debug, Clone, Eq, PartialEq)]
+pub struct Solutions {
+    pub wirings: Vec<Wiring>,
+    /// Whether every consistent wiring is listed. An empty, exhaustive list proves that the
+    /// traffic does not fit the rest of the machine.
+    pub exhaustive: bool,
+}
+
+/// Which window positions of the unknown rotor turn over the rotor to its left.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Turnovers {
+    engaged: [Option<bool>; 26],
+}
+
+/// One observation seen from the unknown rotor: `W(e) = core(W(a))`.
+#[derive(Debug, Copy, Clone)]
+struct Constraint {
+    a: CipherChar,
+    e: CipherChar,
+    core: [CipherChar; 26],
+}
+
+impl Problem {
+    /// `rotors` is every rotor from left to right, with `None` for the unknown one.
+    pub fn new(rotors: Vec<Option<Rotor>>,
+               ring_setting: Alpha,
+               reflector: Reflector)
+               -> Result<Problem, &'static str> {
+        let mut unknown = rotors.iter().enumerate().filter(|&(_, r)| r.is_none()).map(|(i, _)| i);
+        let u = unknown.next().ok_or("Must leave exactly one rotor unknown")?;
+        if unknown.next().is_some() {
+            return Err("Must leave exactly one rotor unknown");
+        }
+        Ok(Problem {
+            rotors,
+            unknown: u,
+            ring_setting: usize::from(ring_setting),
+            reflector,
+            plugboard: Plugboard::default(),
+            entry_wheel: None,
+        })
+    }
+
+    pub fn plugboard(mut self, plugboard: Plugboard) -> Problem {
+        self.plugboard = plugboard;
+        self
+    }
+
+    pub fn entry_wheel(mut self, entry_wheel: EntryWheel) -> Problem {
+        self.entry_wheel = Some(entry_wheel);
+        self
+    }
+
+    /// Finds every wiring of the unknown rotor consistent with the observations, stopping once
+    /// more than `limit` have been found.
+    pub fn solve(&self, observations: &[Observation], limit: usize)
+                 -> Result<Solutions, &'static str> {
+        let mut constraints = Vec::new();
+        for o in observations {
+            constraints.push(self.constraint(o)?);
+        }
+
+        let mut search = Search {
+            constraints: &constraints,
+            limit,
+            solutions: Solutions {
+                wirings: Vec::new(),
+                exhaustive: true,
+            },
+        };
+        let mut wires = [None; 26];
+        if propagate(&constraints, &mut wires) {
+            search.search(wires);
+        }
+        Ok(search.solutions)
+    }
+
+    /// Works out which window positions of the unknown rotor are turnovers, from observations of
+    /// consecutive key presses.
+    ///
+    /// Nothing can be learned about the left-most rotor, which has no rotor to its left to turn.
+    pub fn turnovers(&self, observations: &[Observation]) -> Result<Turnovers, &'static str> {
+        let mut turnovers = Turnovers { engaged: [None; 26] };
+        if self.unknown == 0 {
+            return Ok(turnovers);
+        }
+        let u = self.unknown;
+        let left = self.rotors[u - 1].unwrap();
+        for pair in observations.windows(2) {
+            let before = self.positions(&pair[0])?;
+            let after = self.positions(&pair[1])?;
+            // The left-most rotor has nothing to its left, so its notch does not step it.
+            if u - 1 != 0 && left.notch_engaged(before[u - 1]) {
+                continue;
+            }
+            let stepped = before[u - 1] != after[u - 1];
+            let window = usize::from(pair[0].window_positions[u]);
+            match turnovers.engaged[window] {
+                Some(engaged) if engaged != stepped => {
+                    return Err("Observations disagree about a turnover");
+                }
+                _ => turnovers.engaged[window] = Some(stepped),
+            }
+        }
+        Ok(turnovers)
+    }
+
+    /// The physical position of each rotor, allowing for reversed rotors.
+    fn positions(&self, observation: &Observation) -> Result<Vec<CipherChar>, &'static str> {
+        if observation.window_positions.len() != self.rotors.len() {
+            return Err("Must supply a window position for each rotor");
+        }
+        Ok(self.rotors
+               .iter()
+               .zip(&observation.window_positions)
+               .map(|(r, &l)| match *r {
+                        Some(ref r) if r.is_reversed() => (26 - usize::from(l)) % 26,
+                        _ => usize::from(l),
+                    })
+               .collect())
+    }
+
+    fn constraint(&self, observation: &Observation) -> Result<Constraint, &'static str> {
+        let positions = self.positions(observation)?;
+        let u = self.unknown;
+
+        let forward = |i: usize, c: CipherChar| {
+            let (rotor, p) = (self.rotors[i].unwrap(), positions[i]);
+            (rotor.sub((c + p) % 26) + 26 - p) % 26
+        };
+        let backward = |i: usize, c: CipherChar| {
+            let (rotor, p) = (self.rotors[i].unwrap(), positions[i]);
+            (rotor.inverse_sub((c + p) % 26) + 26 - p) % 26
+        };
+        // From a key to the unknown rotor. The lamp is reached by the reverse path.
+        let inwards = |letter: Alpha| {
+            let mut c = self.plugboard.sub(usize::from(letter));
+            if let Some(etw) = self.entry_wheel {
+                c = etw.sub(c);
+            }
+            for i in (u + 1..self.rotors.len()).rev() {
+                c = forward(i, c);
+            }
+            c
+        };
+
+        let offset = rotor::core_offset(positions[u], self.ring_setting);
+        let mut core = [0; 26];
+        for (y, out) in core.iter_mut().enumerate() {
+            let mut c = (y + 26 - offset) % 26;
+            for i in (0..u).rev() {
+                c = forward(i, c);
+            }
+            c = self.reflector.sub(c);
+            for i in 0..u {
+                c = backward(i, c);
+            }
+            *out = (c + offset) % 26;
+        }
+
+        Ok(Constraint {
+            a: (inwards(observation.plaintext) + offset) % 26,
+            e: (inwards(observation.ciphertext) + offset) % 26,
+            core,
+        })
+    }
+}
+
+struct Search<'a> {
+    constraints: &'a [Constraint],
+    limit: usize,
+    solutions: Solutions,
+}
+
+impl<'a> Search<'a> {
+    fn search(&mut self, wires: [Option<CipherChar>; 26]) {
+        if !self.solutions.exhaustive {
+            return;
+        }
+
+        // Branch on the contact that appears in the most constraints.
+        let mut uses = [0; 26];
+        for c in self.constraints {
+            for &x in &[c.a, c.e] {
+                if wires[x].is_none() {
+                    uses[x] += 1;
+                }
+            }
+        }
+        let (x, &n) = uses.iter().enumerate().max_by_key(|&(_, n)| *n).unwrap();
+        if n == 0 {
+            if self.solutions.wirings.len() == self.limit {
+                self.solutions.exhaustive = false;
+            } else {
+                self.solutions.wirings.push(Wiring::new(&wires));
+            }
+            return;
+        }
+
+        for y in 0..26 {
+            if wires.contains(&Some(y)) {
+                continue;
+            }
+            let mut guess = wires;
+            guess[x] = Some(y);
+            if propagate(self.constraints, &mut guess) {
+                self.search(guess);
+            }
+        }
+    }
+}
+
+/// Follows the constraints from the wires already known, returning false on a contradiction.
+fn propagate(constraints: &[Constraint], wires: &mut [Option<CipherChar>; 26]) -> bool {
+    let assign = |wires: &mut [Option<CipherChar>; 26], x: CipherChar, y: CipherChar| {
+        if wires.contains(&Some(y)) {
+            false
+        } else {
+            wires[x] = Some(y);
+            true
+        }
+    };
+
+    loop {
+        let mut changed = false;
+        for c in constraints {
+            match (wires[c.a], wires[c.e]) {
+                (Some(x), Some(y)) => {
+                    if c.core[x] != y {
+                        return false;
+                    }
+                }
+                (Some(x), None) => {
+                    if !assign(wires, c.e, c.core[x]) {
+                        return false;
+                    }
+                    changed = true;
+                }
+                (None, Some(y)) => {
+                    if !assign(wires, c.a, c.core[y]) {
+                        return false;
+                    }
+                    changed = true;
+                }
+                (None, None) => {}
+            }
+        }
+        if !changed {
+            return true;
+        }
+    }
+}
+
+impl Wiring {
+    fn new(wires: &[Option<CipherChar>; 26]) -> Wiring {
+        let mut w = Wiring { wires: [None; 26] };
+        for (out, wire) in w.wires.iter_mut().zip(wires) {
+            *out = wire.map(|y| Alpha::try_from_usize(y).unwrap());
+        }
+        w
+    }
+
+    /// The letter that `letter` is wired to, if the traffic passed through it.
+    pub fn get(&self, letter: Alpha) -> Option<Alpha> {
+        self.wires[usize::from(letter)]
+    }
+
+    pub fn is_complete(&self) -> bool {
+        self.wires.iter().all(|w| w.is_some())
+    }
+
+    /// A factory for the recovered rotor, if the wiring is complete.
+    pub fn to_factory(&self, turnovers: &Turnovers) -> Option<RotorFactory> {
+        if !self.is_complete() {
+            return None;
+        }
+        let turnovers: String = turnovers.letters().into_iter().map(char::from).collect();
+        Some(RotorFactory::new(&self.to_string(), &turnovers))
+    }
+}
+
+/// The wiring in the usual form, e.g. `EKMFLGDQVZNTOWYHXUSPAIBRCJ`, with `?` for unknown wires.
+impl fmt::Display for Wiring {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for w in &self.wires {
+            write!(f, "{}", w.map(char::from).unwrap_or('?'))?;
+        }
+        Ok(())
+    }
+}
+
+impl Turnovers {
+    /// Whether the rotor turns over at `window`, if the traffic shows it.
+    pub fn get(&self, window: Alpha) -> Option<bool> {
+        self.engaged[usize::from(window)]
+    }
+
+    /// The window positions known to be turnovers.
+    pub fn letters(&self) -> Vec<Alpha> {
+        self.filter(Some(true))
+    }
+
+    /// The window positions which the traffic says nothing about.
+    pub fn unknown(&self) -> Vec<Alpha> {
+        self.filter(None)
+    }
+
+    fn filter(&self, value: Option<bool>) -> Vec<Alpha> {
+        Alpha::all().filter(|&l| self.get(l) == value).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use alpha::Alpha::*;
+    use reflector::Reflectors;
+    use rotor::Rotors;
+
+    fn problem() -> Problem {
+        let rotors = Rotors::default();
+        Problem::new(vec![Some(rotors.i.with_ring_setting(A)),
+                          Some(rotors.ii.with_ring_setting(A)),
+                          None],
+                     A,
+                     Reflectors::default().b)
+                .unwrap()
+    }
+
+    #[test]
+    fn one_unknown_rotor() {
+        let rotor = Rotors::default().i.with_ring_setting(A);
+        let reflector = Reflectors::default().b;
+        assert!(Problem::new(vec![Some(rotor), Some(rotor)], A, reflector).is_err());
+        assert!(Problem::new(vec![None, Some(rotor), None], A, reflector).is_err());
+    }
+
+    #[test]
+    fn letter_never_enciphers_to_itself() {
+        let observation = Observation {
+            window_positions: vec![A, A, B],
+            plaintext: K,
+            ciphertext: K,
+        };
+        let solutions = problem().solve(&[observation], 10).unwrap();
+        assert!(solutions.wirings.is_empty());
+        assert!(solutions.exhaustive);
+    }
+
+    #[test]
+    fn limit() {
+        let observation = Observation {
+            window_positions: vec![A, A, B],
+            plaintext: K,
+            ciphertext: L,
+        };
+        let solutions = problem().solve(&[observation], 5).unwrap();
+        assert_eq!(5, solutions.wirings.len());
+        assert!(!solutions.exhaustive);
+        assert_eq!(2, solutions.wirings[0].to_string().chars().filter(|&c| c != '?').count());
+    }
+}
@@ -0,0 +1,144 @@
+#![cfg(feature = "std")]
+
+extern crate enigma;
+
+use enigma::Alpha;
+use enigma::EnigmaBuilder;
+use enigma::Reflectors;
+use enigma::Rotors;
+use enigma::banburismus;
+use enigma::banburismus::Alignment;
+use enigma::banburismus::Evidence;
+use enigma::banburismus::Message;
+use enigma::banburismus::Model;
+
+const PLAINTEXT: &str = "
+    It was the best of times it was the worst of times it was the age of wisdom it was the age of
+    foolishness it was the epoch of belief it was the epoch of incredulity it was the season of
+    light it was the season of darkness it was the spring of hope it was the winter of despair we
+    had everything before us we had nothing before us we were all going direct to heaven we were
+    all going direct the other way in short the period was so far like the present period that
+    some of its noisiest authorities insisted on its being received for good or for evil in the
+    superlative degree of comparison only there were a king with a large jaw and a queen with a
+    plain face on the throne of england there were a king with a large jaw and a queen with a fair
+    face on the throne of france in both countries it was clearer than crystal to the lords of
+    the state preserves of loaves and fishes that things in general were settled for ever it was
+    the year of our lord one thousand seven hundred and seventy five spiritual revelations were
+    conceded to england at that favoured period as at this mrs southcott had recently attained
+    her five and twentieth blessed birthday of whom a prophetic private in the life guards had
+    heralded the sublime appearance by announcing that arrangements were made for the swallowing
+    up of london and westminster even the cock lane ghost had been laid only a round dozen of
+    years after rapping out its messages as the spirits of this very year last past rapped out
+    theirs the earthly order of events had lately come to the english crown and people from a
+    congress of british subjects in america which strange to relate have proved more important
+    to the human race than any communications yet received through any of the chickens of the
+    cock lane brood";
+
+/// A linear congruential generator, so the traffic is the same on every run.
+struct Lcg(u64);
+
+impl Lcg {
+    fn next(&mut self, n: usize) -> usize {
+        self.0 = self.0.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        ((self.0 >> 33) % n as u64) as usize
+    }
+}
+
+struct Traffic {
+    messages: Vec<Message>,
+    /// The key letter behind each indicator letter, for the middle and right wheels.
+    middle_keys: [Option<Alpha>; 26],
+    right_keys: [Option<Alpha>; 26],
+}
+
+/// A day's traffic on rotors I II IV, with message keys enciphered at the Grundstellung XQR.
+fn traffic() -> Traffic {
+    use enigma::Alpha::*;
+
+    let rotors = Rotors::default();
+    let mut enigma = EnigmaBuilder::default()
+        .left_rotor(rotors.i.with_ring_setting(A))
+        .mid_rotor(rotors.ii.with_ring_setting(A))
+        .right_rotor(rotors.iv.with_ring_setting(A))
+        .window_positions([X, Q, R])
+        .reflector(Reflectors::default().b)
+        .build()
+        .unwrap();
+
+    let plaintext = Alpha::from_string(PLAINTEXT);
+    let mut rng = Lcg(1);
+    let mut traffic = Traffic {
+        messages: Vec::new(),
+        middle_keys: [None; 26],
+        right_keys: [None; 26],
+    };
+    for _ in 0..150 {
+        let key = [[C, H][rng.next(2)],
+                   [K, L, M][rng.next(3)],
+                   Alpha::try_from_usize(rng.next(26)).unwrap()];
+        enigma.reset();
+        let indicator = enigma.message(&key);
+        traffic.middle_keys[usize::from(indicator[1])] = Some(key[1]);
+        traffic.right_keys[usize::from(indicator[2])] = Some(key[2]);
+
+        let start = rng.next(plaintext.len() - 250);
+        let len = 150 + rng.next(100);
+        let mut machine = enigma;
+        machine.set_window_positions(&key);
+        traffic.messages.push(Message {
+            indicator: [indicator[0], indicator[1], indicator[2]],
+            text: machine.message(&plaintext[start..start + len]),
+        });
+    }
+    traffic
+}
+
+fn right_wheel_alignment(evidence: &Evidence) -> Alignment {
+    let chains = banburismus::chain(evidence, 10.0);
+    let absolute = chains[0].absolute();
+    assert_eq!(1, absolute.len());
+    absolute[0]
+}
+
+#[test]
+fn recovers_right_wheel() {
+    let traffic = traffic();
+    let model = Model::default();
+    let evidence = banburismus::right_wheel_evidence(&model, &traffic.messages);
+
+    let alignment = right_wheel_alignment(&evidence);
+    assert_eq!(26, alignment.letters().len());
+    for l in alignment.letters() {
+        assert_eq!(traffic.right_keys[usize::from(l)], alignment.get(l));
+    }
+
+    let rotors = Rotors::default();
+    let candidates = [rotors.i, rotors.ii, rotors.iii, rotors.iv, rotors.v];
+    let ranked = banburismus::rank_wheels(&evidence, &alignment, &candidates);
+    assert_eq!(3, ranked[0].0);
+}
+
+#[test]
+fn aligns_middle_wheel() {
+    let traffic = traffic();
+    let model = Model::default();
+    let evidence = banburismus::right_wheel_evidence(&model, &traffic.messages);
+    let right = right_wheel_alignment(&evidence);
+
+    let turnovers = Rotors::default().iv.turnovers();
+    let evidence =
+        banburismus::middle_wheel_evidence(&model, &traffic.messages, &right, &turnovers);
+    let chains = banburismus::chain(&evidence, 10.0);
+
+    // The middle letters of the keys were K, L and M, and the chain places them the same
+    // distances apart.
+    let chain = chains[0];
+    assert_eq!(3, chain.letters().len());
+    for p in chain.letters() {
+        for q in chain.letters() {
+            let keys = &traffic.middle_keys;
+            assert_eq!(keys[usize::from(q)].unwrap() - keys[usize::from(p)].unwrap(),
+                       chain.get(q).unwrap() - chain.get(p).unwrap());
+        }
+    }
+}
@@ -0,0 +1,67 @@
+#![cfg(feature = "ffi")]
+
+extern crate enigma;
+
+use std::ffi::CStr;
+use std::os::raw::c_char;
+use std::os::raw::c_int;
+use std::ptr;
+
+use enigma::ffi::*;
+
+fn new(key: &[u8]) -> Result<*mut EnigmaHandle, EnigmaStatus> {
+    let mut handle = ptr::null_mut();
+    match unsafe { enigma_new(key.as_ptr() as *const c_char, &mut handle) } {
+        EnigmaStatus::Ok => Ok(handle),
+        status => Err(status),
+    }
+}
+
+fn positions(handle: *const EnigmaHandle) -> String {
+    let mut buffer = [0 as c_char; 4];
+    assert_eq!(EnigmaStatus::Ok,
+               unsafe { enigma_get_positions(handle, buffer.as_mut_ptr(), buffer.len()) });
+    unsafe { CStr::from_ptr(buffer.as_ptr()) }.to_str().unwrap().to_string()
+}
+
+#[test]
+fn enciphers() {
+    let handle = new(b"B I II III / 01 01 01 / / AAA\0").unwrap();
+
+    let mut lamp = 0;
+    assert_eq!(EnigmaStatus::Ok, unsafe { enigma_press(handle, b'a' as c_char, &mut lamp) });
+    assert_eq!(b'B' as c_char, lamp);
+    assert_eq!("AAB", positions(handle));
+
+    let mut buffer = *b"aaa, a";
+    let p = buffer.as_mut_ptr() as *mut c_char;
+    assert_eq!(EnigmaStatus::Ok,
+               unsafe { enigma_encipher(handle, p, p, buffer.len()) });
+    assert_eq!(b"DZG, O", &buffer);
+
+    assert_eq!(EnigmaStatus::Ok, unsafe { enigma_reset(handle) });
+    assert_eq!("AAA", positions(handle));
+    unsafe { enigma_free(handle) };
+}
+
+#[test]
+fn errors() {
+    assert_eq!(Err(EnigmaStatus::InvalidKey), new(b"B I II IX / 01 01 01 / / AAA\0"));
+    assert_eq!(Err(EnigmaStatus::InvalidPlugboard), new(b"B I II III / 01 01 01 / AB BC / AAA\0"));
+    assert_eq!(Err(EnigmaStatus::InvalidUtf8), new(b"B I II III / 01 01 01 / / AA\xff\0"));
+    assert_eq!(EnigmaStatus::NullPointer, unsafe { enigma_reset(ptr::null_mut()) });
+
+    let handle = new(b"B I II III / 01 01 01 / / AAA\0").unwrap();
+    let mut lamp = 0;
+    assert_eq!(EnigmaStatus::InvalidCharacter,
+               unsafe { enigma_press(handle, b'1' as c_char, &mut lamp) });
+    let mut small = [0 as c_char; 3];
+    assert_eq!(EnigmaStatus::BufferTooSmall,
+               unsafe { enigma_get_positions(handle, small.as_mut_ptr(), small.len()) });
+    unsafe { enigma_free(handle) };
+
+    let message = |status| unsafe { CStr::from_ptr(enigma_status_message(status)) };
+    assert_eq!("invalid key", message(EnigmaStatus::InvalidKey as c_int).to_str().unwrap());
+    assert_eq!("unknown status", message(8).to_str().unwrap());
+    assert_eq!("unknown status", message(-1).to_str().unwrap());
+}
@@ -1,3 +1,5 @@
+#![cfg(feature = "alloc")]
+
 extern crate enigma;
 
 use enigma::EnigmaBuilder;
@@ -5,6 +7,10 @@ use enigma::Alpha;
 use enigma::alphas_to_string;
 use enigma::Rotors;
 use enigma::Reflectors;
+use enigma::Plugboard;
+use enigma::Reflector;
+use enigma::RotorFactory;
+use enigma::TypexBuilder;
 
 #[test]
 fn reciprocality() {
@@ -50,6 +56,23 @@ fn simple_cipher() {
     assert_eq!("BDZGO", alphas_to_string(&ciphertext))
 }
 
+#[test]
+fn ring_settings() {
+    use enigma::Alpha::*;
+
+    let rotors = Rotors::default();
+    let mut enigma = EnigmaBuilder::default()
+        .left_rotor(rotors.i.with_ring_setting(B))
+        .mid_rotor(rotors.ii.with_ring_setting(B))
+        .right_rotor(rotors.iii.with_ring_setting(B))
+        .window_positions([A, A, A])
+        .reflector(Reflectors::default().b)
+        .build()
+        .unwrap();
+
+    assert_eq!("EWTYX", alphas_to_string(&enigma.message(&[A, A, A, A, A])));
+}
+
 #[test]
 fn stepping() {
     use enigma::Alpha::*;
@@ -66,13 +89,13 @@ fn stepping() {
         .unwrap();
 
     enigma.press(A);
-    assert_eq!([A, A, V], enigma.get_window_positions());
+    assert_eq!(vec![A, A, V], enigma.get_window_positions());
 
     enigma.press(A);
-    assert_eq!([A, B, W], enigma.get_window_positions());
+    assert_eq!(vec![A, B, W], enigma.get_window_positions());
 
     enigma.press(A);
-    assert_eq!([A, B, X], enigma.get_window_positions());
+    assert_eq!(vec![A, B, X], enigma.get_window_positions());
 }
 
 #[test]
@@ -91,14 +114,488 @@ fn double_stepping() {
         .unwrap();
 
     enigma.press(A);
-    assert_eq!([A, D, V], enigma.get_window_positions());
+    assert_eq!(vec![A, D, V], enigma.get_window_positions());
+
+    enigma.press(A);
+    assert_eq!(vec![A, E, W], enigma.get_window_positions());
+
+    enigma.press(A);
+    assert_eq!(vec![B, F, X], enigma.get_window_positions());
+
+    enigma.press(A);
+    assert_eq!(vec![B, F, Y], enigma.get_window_positions());
+}
+
+#[test]
+fn plugboard() {
+    use enigma::Alpha::*;
+
+    let rotors = Rotors::default();
+    let reflectors = Reflectors::default();
+    let builder = EnigmaBuilder::default()
+        .left_rotor(rotors.i.with_ring_setting(A))
+        .mid_rotor(rotors.ii.with_ring_setting(A))
+        .right_rotor(rotors.iii.with_ring_setting(A))
+        .window_positions([A, A, A])
+        .reflector(reflectors.b);
+    let mut unplugged = builder.build().unwrap();
+    let mut plugged = builder.plugboard(Plugboard::new(&[(A, B), (D, Z)]).unwrap())
+        .build()
+        .unwrap();
+
+    // AAAAA -> BDZGO without a plugboard, so swapping A/B on the way in and B/A, D/Z, Z/D on
+    // the way out gives:
+    assert_eq!("BDZGO", alphas_to_string(&unplugged.message(&[A, A, A, A, A])));
+    assert_eq!("AZDGO", alphas_to_string(&plugged.message(&[B, B, B, B, B])));
+}
+
+#[test]
+fn trace() {
+    use enigma::Alpha::*;
+    use enigma::Component;
+    use enigma::Hop;
+
+    let rotors = Rotors::default();
+    let reflectors = Reflectors::default();
+    let mut enigma = EnigmaBuilder::default()
+        .left_rotor(rotors.i.with_ring_setting(A))
+        .mid_rotor(rotors.ii.with_ring_setting(A))
+        .right_rotor(rotors.iii.with_ring_setting(A))
+        .window_positions([A, A, A])
+        .reflector(reflectors.b)
+        .build()
+        .unwrap();
+
+    let trace = enigma.trace(A);
+
+    assert_eq!(B, trace.lamp);
+    assert_eq!(vec![A, A, A], trace.positions_before);
+    assert_eq!(vec![A, A, B], trace.positions_after);
+    assert_eq!(9, trace.hops.len());
+    assert_eq!(Hop {
+                   component: Component::RotorForward(2),
+                   offset: Some(B),
+                   input: A,
+                   output: C,
+               },
+               trace.hops[1]);
+    assert_eq!(trace.lamp, trace.hops[8].output);
+
+    enigma.reset();
+    let lamps: Vec<Alpha> = (0..5).map(|_| enigma.trace(A).lamp).collect();
+    assert_eq!("BDZGO", alphas_to_string(&lamps));
+}
+
+#[test]
+fn rotor_list() {
+    use enigma::Alpha::*;
+
+    let rotors = Rotors::default();
+    let reflectors = Reflectors::default();
+    let mut enigma = EnigmaBuilder::default()
+        .rotors(vec![rotors.i.with_ring_setting(A),
+                     rotors.ii.with_ring_setting(A),
+                     rotors.iii.with_ring_setting(A)])
+        .window_positions([A, A, A])
+        .reflector(reflectors.b)
+        .build()
+        .unwrap();
+
+    let plaintext = Alpha::from_string("AAAAA");
+    let ciphertext = enigma.message(&plaintext);
+
+    assert_eq!("BDZGO", alphas_to_string(&ciphertext))
+}
+
+#[test]
+fn four_stepping_rotors() {
+    use enigma::Alpha::*;
+
+    let rotors = Rotors::default();
+    let reflectors = Reflectors::default();
+    let mut enigma = EnigmaBuilder::default()
+        .rotors(vec![rotors.i.with_ring_setting(A),
+                     rotors.i.with_ring_setting(A),
+                     rotors.ii.with_ring_setting(A),
+                     rotors.iii.with_ring_setting(A)])
+        .window_positions([A, P, D, U])
+        .reflector(reflectors.b)
+        .build()
+        .unwrap();
+
+    enigma.press(A);
+    assert_eq!(vec![A, P, D, V], enigma.get_window_positions());
+
+    enigma.press(A);
+    assert_eq!(vec![A, P, E, W], enigma.get_window_positions());
+
+    enigma.press(A);
+    assert_eq!(vec![A, Q, F, X], enigma.get_window_positions());
+
+    enigma.press(A);
+    assert_eq!(vec![B, R, F, Y], enigma.get_window_positions());
+}
+
+#[test]
+fn fixed_rotor() {
+    use enigma::Alpha::*;
+
+    let rotors = Rotors::default();
+    let reflectors = Reflectors::default();
+    let mut enigma = EnigmaBuilder::default()
+        .fixed_rotors(vec![rotors.iv.with_ring_setting(A)])
+        .rotors(vec![rotors.i.with_ring_setting(A),
+                     rotors.ii.with_ring_setting(A),
+                     rotors.iii.with_ring_setting(A)])
+        .window_positions([Z, A, D, U])
+        .reflector(reflectors.b)
+        .build()
+        .unwrap();
+
+    enigma.message(&[A, A, A]);
+    assert_eq!(vec![Z, B, F, X], enigma.get_window_positions());
+
+    let mut unstepped = enigma;
+    let ciphertext = enigma.message(&Alpha::from_string("helloworld"));
+    let deciphered = unstepped.message(&ciphertext);
+    assert_eq!("HELLOWORLD", alphas_to_string(&deciphered));
+}
+
+#[test]
+fn mismatched_window_positions() {
+    use enigma::Alpha::*;
+
+    let rotors = Rotors::default();
+    let reflectors = Reflectors::default();
+    let result = EnigmaBuilder::default()
+        .rotors(vec![rotors.i.with_ring_setting(A), rotors.ii.with_ring_setting(A)])
+        .window_positions([A, A, A])
+        .reflector(reflectors.b)
+        .build();
+
+    assert!(result.is_err());
+}
+
+fn typex_rotor(wiring: &str) -> RotorFactory {
+    RotorFactory::new(wiring, "BN")
+}
+
+#[test]
+fn typex_stepping() {
+    use enigma::Alpha::*;
+
+    let mut typex = TypexBuilder::default()
+        .rotors([typex_rotor("EKMFLGDQVZNTOWYHXUSPAIBRCJ").with_ring_setting(A),
+                 typex_rotor("AJDKSIRUXBLHWTMCQGZNPYFVOE").with_ring_setting(A),
+                 typex_rotor("BDFHJLCPRTXVZNYEIWGAKMUSQO").with_ring_setting(A)])
+        .stators([typex_rotor("ESOVPZJAYQUIRHXLNFTGKDCMWB").with_ring_setting(A),
+                  typex_rotor("VZBRGITYUPSDNHLXAWMJQOFECK").with_ring_setting(A)])
+        .window_positions([A, A, A, K, L])
+        .reflector(Reflectors::default().b)
+        .build()
+        .unwrap();
+
+    typex.press(A);
+    assert_eq!(vec![A, A, B, K, L], typex.get_window_positions());
+
+    typex.press(A);
+    assert_eq!(vec![A, B, C, K, L], typex.get_window_positions());
+
+    typex.press(A);
+    assert_eq!(vec![B, C, D, K, L], typex.get_window_positions());
+}
+
+#[test]
+fn typex_reversed_rotors() {
+    use enigma::Alpha::*;
+
+    let reflector = Reflector::from_wiring("YRUHQSLDPXNGOKMIEBFZCWVJAT");
+    let builder = TypexBuilder::default()
+        .rotors([typex_rotor("EKMFLGDQVZNTOWYHXUSPAIBRCJ").with_ring_setting(A),
+                 typex_rotor("AJDKSIRUXBLHWTMCQGZNPYFVOE").with_ring_setting(A).reversed(),
+                 typex_rotor("BDFHJLCPRTXVZNYEIWGAKMUSQO").with_ring_setting(A).reversed()])
+        .stators([typex_rotor("ESOVPZJAYQUIRHXLNFTGKDCMWB").with_ring_setting(A).reversed(),
+                  typex_rotor("VZBRGITYUPSDNHLXAWMJQOFECK").with_ring_setting(A)])
+        .window_positions([A, A, C, K, L])
+        .reflector(reflector);
+    let mut typex = builder.clone().build().unwrap();
+
+    typex.press(A);
+    assert_eq!(vec![A, A, B, K, L], typex.get_window_positions());
+
+    typex.reset();
+    let plaintext = Alpha::from_string("thequickbrownfoxjumpsoverthelazydog");
+    let ciphertext = typex.message(&plaintext);
+    typex.reset();
+    assert_eq!(alphas_to_string(&plaintext),
+               alphas_to_string(&typex.message(&ciphertext)));
+
+    let mut forwards = builder
+        .rotors([typex_rotor("EKMFLGDQVZNTOWYHXUSPAIBRCJ").with_ring_setting(A),
+                 typex_rotor("AJDKSIRUXBLHWTMCQGZNPYFVOE").with_ring_setting(A),
+                 typex_rotor("BDFHJLCPRTXVZNYEIWGAKMUSQO").with_ring_setting(A)])
+        .build()
+        .unwrap();
+    assert!(alphas_to_string(&ciphertext) != alphas_to_string(&forwards.message(&plaintext)));
+}
+
+#[test]
+fn reversed_rotor_stepping() {
+    use enigma::Alpha::*;
+    use enigma::Orientation::*;
+
+    let rotors = Rotors::default();
+    let reflectors = Reflectors::default();
+    let mut enigma = EnigmaBuilder::default()
+        .left_rotor(rotors.i.with_orientation(A, Forward))
+        .mid_rotor(rotors.ii.with_orientation(A, Forward))
+        .right_rotor(rotors.i.with_orientation(A, Reversed))
+        .window_positions([A, A, H])
+        .reflector(reflectors.b)
+        .build()
+        .unwrap();
+
+    // Reversed, rotor I's ring reads backwards and its notch engages at G rather than Q.
+    enigma.press(A);
+    assert_eq!(vec![A, A, G], enigma.get_window_positions());
 
     enigma.press(A);
-    assert_eq!([A, E, W], enigma.get_window_positions());
+    assert_eq!(vec![A, B, F], enigma.get_window_positions());
 
     enigma.press(A);
-    assert_eq!([B, F, X], enigma.get_window_positions());
+    assert_eq!(vec![A, B, E], enigma.get_window_positions());
+
+    enigma.reset();
+    let plaintext = Alpha::from_string("helloworld");
+    let ciphertext = enigma.message(&plaintext);
+    enigma.reset();
+    assert_eq!(alphas_to_string(&plaintext),
+               alphas_to_string(&enigma.message(&ciphertext)));
+}
+
+fn m3(positions: [Alpha; 3]) -> enigma::Enigma {
+    let rotors = Rotors::default();
+    EnigmaBuilder::default()
+        .left_rotor(rotors.i.with_ring_setting(Alpha::A))
+        .mid_rotor(rotors.ii.with_ring_setting(Alpha::A))
+        .right_rotor(rotors.iii.with_ring_setting(Alpha::A))
+        .window_positions(positions)
+        .reflector(Reflectors::default().b)
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn m3_period() {
+    use enigma::Alpha::*;
+
+    let cycle = m3([A, A, A]).cycle();
+    assert_eq!(0, cycle.transient);
+    assert_eq!(16900, cycle.period);
+    assert_eq!(vec![A, A, B], cycle.positions[1]);
+}
+
+#[test]
+fn unreachable_positions() {
+    use enigma::Alpha::*;
+
+    // Rotor II only rests on its turnover E for a single key press, just after the right-hand
+    // rotor turns it over.
+    let cycle = m3([A, E, A]).cycle();
+    assert_eq!(1, cycle.transient);
+    assert_eq!(16900, cycle.period);
+    assert_eq!(vec![B, F, B], cycle.cycle()[0]);
+    assert!(!cycle.cycle().contains(&vec![A, E, A]));
+    assert!(cycle.cycle().contains(&vec![A, E, W]));
+}
+
+#[test]
+fn multiple_notch_period() {
+    use enigma::Alpha::*;
+
+    let rotors = Rotors::default();
+    let mut enigma = EnigmaBuilder::default()
+        .rotors(vec![rotors.i.with_ring_setting(A),
+                     RotorFactory::new("AJDKSIRUXBLHWTMCQGZNPYFVOE", "EM").with_ring_setting(A),
+                     RotorFactory::new("BDFHJLCPRTXVZNYEIWGAKMUSQO", "DQV").with_ring_setting(A)])
+        .window_positions([A, A, A])
+        .reflector(Reflectors::default().b)
+        .build()
+        .unwrap();
 
+    let cycle = enigma.cycle();
+    assert_eq!(0, cycle.transient);
+    assert_eq!(2704, cycle.period);
+    assert_eq!(cycle.transient + cycle.period, cycle.positions.len());
+    for positions in &cycle.positions[1..] {
+        enigma.press(A);
+        assert_eq!(positions, &enigma.get_window_positions());
+    }
     enigma.press(A);
-    assert_eq!([B, F, Y], enigma.get_window_positions());
+    assert_eq!(cycle.cycle()[0], enigma.get_window_positions());
+}
+
+#[test]
+fn encipher_str() {
+    use enigma::NonAlphabetic;
+
+    let mut enigma = m3([Alpha::A, Alpha::A, Alpha::A]);
+    let ciphertext = enigma.encipher_str("Aa, aa a!", NonAlphabetic::PassThrough).unwrap();
+    assert_eq!("BD, ZG O!", ciphertext);
+    enigma.reset();
+    assert_eq!("AA, AA A!", enigma.encipher_str(&ciphertext, NonAlphabetic::PassThrough).unwrap());
+
+    enigma.reset();
+    assert_eq!("BDZGO", enigma.encipher_str("Aa, aa a!", NonAlphabetic::Drop).unwrap());
+
+    enigma.reset();
+    let error = enigma.encipher_str("Aa, aa a!", NonAlphabetic::Error).unwrap_err();
+    assert_eq!(2, error.position);
+    assert_eq!(vec![Alpha::A, Alpha::A, Alpha::A], enigma.get_window_positions());
+}
+
+#[test]
+fn encipher_without_allocating() {
+    use enigma::Alpha::*;
+
+    let mut enigma = m3([A, A, A]);
+    let mut output = [A; 5];
+    enigma.encipher_into(&[A; 5], &mut output);
+    assert_eq!([B, D, Z, G, O], output);
+    assert_eq!(F, enigma.window_position(2));
+
+    enigma.reset();
+    enigma.encipher_in_place(&mut output);
+    assert_eq!([A; 5], output);
+}
+
+#[test]
+fn too_many_rotors() {
+    let rotor = Rotors::default().i.with_ring_setting(Alpha::A);
+    let result = EnigmaBuilder::default()
+        .rotors(vec![rotor; enigma::MAX_ROTORS + 1])
+        .window_positions(vec![Alpha::A; enigma::MAX_ROTORS + 1])
+        .reflector(Reflectors::default().b)
+        .build();
+    assert_eq!("Too many rotors: at most MAX_ROTORS (8), counting fixed wheels",
+               result.unwrap_err());
+}
+
+#[test]
+fn snapshot_and_restore() {
+    use enigma::Alpha::*;
+    use enigma::Difference;
+
+    let mut enigma = enigma::key::parse("B I II III / 01 01 01 / AM FI NV / ADO").unwrap()
+        .build()
+        .unwrap();
+    let plaintext = Alpha::from_string("KEINEBESONDERENEREIGNISSE");
+    let ciphertext = enigma.message(&plaintext);
+    enigma.reset();
+
+    // Stop part way through the message, crossing a double step, then pick it up again.
+    let first = enigma.message(&plaintext[..10]);
+    let checkpoint = enigma.snapshot();
+    assert_eq!(&[B, F, Y], checkpoint.window_positions());
+    enigma.message(&[X; 30]);
+    assert_ne!(checkpoint, enigma.snapshot());
+    enigma.restore(&checkpoint).unwrap();
+    assert_eq!(checkpoint, enigma.snapshot());
+    let rest = enigma.message(&plaintext[10..]);
+    assert_eq!(ciphertext, [first, rest].concat());
+
+    // `reset` still returns to the start of the message.
+    enigma.reset();
+    let differences: Vec<_> = enigma.snapshot().diff(&checkpoint).collect();
+    assert_eq!(vec![Difference::WindowPosition { slot: 0, from: A, to: B },
+                    Difference::WindowPosition { slot: 1, from: D, to: F },
+                    Difference::WindowPosition { slot: 2, from: O, to: Y }],
+               differences);
+}
+
+#[test]
+fn restore_checks_settings() {
+    use enigma::Alpha::*;
+    use enigma::Difference;
+
+    let key = |k| enigma::key::parse(k).unwrap().build().unwrap();
+    let mut enigma = key("B I II III / 01 01 01 / AM FI NV / ADO");
+    let checkpoint = enigma.snapshot();
+
+    let mut other = key("B I II IV / 01 01 02 / AM FI / ADO");
+    assert!(other.restore(&checkpoint).is_err());
+    let differences: Vec<_> = other.snapshot().diff(&checkpoint).collect();
+    assert_eq!(vec![Difference::Wheels,
+                    Difference::RingSetting { slot: 2, from: B, to: A },
+                    Difference::Plug { letter: N, from: N, to: V },
+                    Difference::Plug { letter: V, from: V, to: N }],
+               differences);
+
+    let mut reflector = key("C I II III / 01 01 01 / AM FI NV / ADO");
+    assert!(reflector.restore(&checkpoint).is_err());
+    assert_eq!(vec![Difference::Reflector],
+               reflector.snapshot().diff(&checkpoint).collect::<Vec<_>>());
+
+    let mut four = key("B thin Beta I II III / 01 01 01 01 / AM FI NV / AADO");
+    assert!(four.restore(&checkpoint).is_err());
+    enigma.message(&[X; 5]);
+    assert!(enigma.restore(&checkpoint).is_ok());
+}
+
+#[test]
+fn resume_from_written_state() {
+    let key = "B II IV V / 02 21 12 / AV BS CG DL FU HZ IN KM OW RX / BLA";
+    let plaintext = Alpha::from_string("KEINEBESONDERENEREIGNISSE");
+    let mut enigma = enigma::key::parse(key).unwrap().build().unwrap();
+    let ciphertext = enigma.message(&plaintext);
+
+    // Write the state out part way through, then pick the message up on a new machine.
+    enigma.reset();
+    let first = enigma.message(&plaintext[..12]);
+    let saved = enigma.snapshot().to_string();
+    assert!(saved.ends_with(" / BUL / AV BS CG DL FU HZ IN KM OW RX / BLM / A"));
+
+    let mut resumed = enigma::key::parse(key).unwrap().build().unwrap();
+    let state = enigma::MachineState::parse(&saved).unwrap();
+    resumed.restore(&state).unwrap();
+    let rest = resumed.message(&plaintext[12..]);
+    assert_eq!(ciphertext, [first, rest].concat());
+}
+
+#[test]
+fn observer() {
+    use enigma::Alpha::*;
+    use enigma::Event;
+    use enigma::Event::*;
+
+    let mut enigma = enigma::key::parse("B I II III / 01 01 01 / / ADU").unwrap()
+        .build()
+        .unwrap();
+    let mut events = Vec::new();
+    let mut lamps = Vec::new();
+    {
+        let mut record = |e: Event| events.push(e);
+        for _ in 0..3 {
+            lamps.push(enigma.press_observed(A, &mut record));
+        }
+        enigma.reset_observed(&mut record);
+    }
+    assert_eq!(vec![Step { slot: 2, window: V },
+                    Lamp { key: A, lamp: lamps[0] },
+                    Turnover { slot: 2 },
+                    Step { slot: 1, window: E },
+                    Step { slot: 2, window: W },
+                    Lamp { key: A, lamp: lamps[1] },
+                    Turnover { slot: 1 },
+                    DoubleStep { slot: 1 },
+                    Step { slot: 0, window: B },
+                    Step { slot: 1, window: F },
+                    Step { slot: 2, window: X },
+                    Lamp { key: A, lamp: lamps[2] },
+                    Reset],
+               events);
+
+    // Observing doesn't change the cipher.
+    let mut unobserved = enigma;
+    assert_eq!(lamps, unobserved.message(&[A, A, A]));
 }
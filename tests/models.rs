@@ -0,0 +1,209 @@
+#![cfg(feature = "alloc")]
+
+extern crate enigma;
+
+use enigma::Alpha;
+use enigma::Alpha::*;
+use enigma::EnigmaBuilder;
+use enigma::EntryWheel;
+use enigma::Event;
+use enigma::MachineModel;
+use enigma::Plugboard;
+use enigma::Rotor;
+
+fn rotors(model: MachineModel, names: &[&str]) -> Vec<Rotor> {
+    names.iter().map(|n| model.rotor(n).unwrap().with_ring_setting(A)).collect()
+}
+
+#[test]
+fn m3_matches_enigma_i() {
+    let model = MachineModel::M3;
+    let mut enigma = EnigmaBuilder::for_model(model)
+        .rotors(rotors(model, &["I", "II", "III"]))
+        .reflector(model.reflector("B").unwrap())
+        .window_positions([A, A, A])
+        .build()
+        .unwrap();
+    assert_eq!(Alpha::from_string("BDZGO"), enigma.message(&[A; 5]));
+}
+
+#[test]
+fn m4_with_beta_at_a_matches_m3() {
+    // The thin B reflector and Beta at A, with ring setting A, were made to match reflector B.
+    let model = MachineModel::M4;
+    let mut enigma = EnigmaBuilder::for_model(model)
+        .fixed_rotors(rotors(model, &["Beta"]))
+        .rotors(rotors(model, &["I", "II", "III"]))
+        .reflector(model.reflector("B thin").unwrap())
+        .window_positions([A, A, A, A])
+        .build()
+        .unwrap();
+    assert_eq!(Alpha::from_string("BDZGO"), enigma.message(&[A; 5]));
+}
+
+#[test]
+fn rejects_foreign_parts() {
+    let m3 = MachineModel::M3;
+    let builder = EnigmaBuilder::for_model(MachineModel::EnigmaI)
+        .rotors(rotors(m3, &["I", "II", "III"]))
+        .reflector(m3.reflector("B").unwrap())
+        .window_positions([A, A, A]);
+    assert!(builder.build().is_ok());
+
+    let vi = builder.rotors(rotors(m3, &["I", "II", "VI"]));
+    assert_eq!("Rotor was not issued with this model", vi.build().unwrap_err());
+
+    let norway = builder.rotors(rotors(MachineModel::Norway, &["I", "II", "III"]));
+    assert_eq!("Rotor was not issued with this model", norway.build().unwrap_err());
+
+    let reversed = builder.rotors(vec![m3.rotor("I").unwrap().with_ring_setting(A).reversed(),
+                                       m3.rotor("II").unwrap().with_ring_setting(A),
+                                       m3.rotor("III").unwrap().with_ring_setting(A)]);
+    assert_eq!("Rotor was not issued with this model", reversed.build().unwrap_err());
+
+    let thin = builder.reflector(MachineModel::M4.reflector("B thin").unwrap());
+    assert_eq!("Reflector was not issued with this model", thin.build().unwrap_err());
+
+    let four = builder.rotors(rotors(m3, &["I", "II", "III", "IV"])).window_positions([A; 4]);
+    assert_eq!("Wrong number of rotors for this model", four.build().unwrap_err());
+
+    let etw = builder.entry_wheel(EntryWheel::from_wiring("QWERTZUIOASDFGHJKPYXCVBNML"));
+    assert_eq!("Entry wheel does not belong to this model", etw.build().unwrap_err());
+
+    let set = builder.reflector_position(B);
+    assert_eq!("This model's reflector cannot be set", set.build().unwrap_err());
+}
+
+#[test]
+fn commercial_machines_have_no_plugboard() {
+    let k = MachineModel::K;
+    let builder = EnigmaBuilder::for_model(k)
+        .rotors(rotors(k, &["III", "I", "II"]))
+        .reflector(k.reflector("UKW").unwrap())
+        .window_positions([A, A, A]);
+    assert!(builder.plugboard(Plugboard::default()).build().is_ok());
+
+    let plugged = builder.plugboard(Plugboard::new(&[(A, B)]).unwrap());
+    assert_eq!("This model has no plugboard", plugged.build().unwrap_err());
+
+    let m4_greek_wheel = builder.fixed_rotors(rotors(MachineModel::M4, &["Beta"]));
+    assert_eq!("Wrong number of rotors for this model", m4_greek_wheel.build().unwrap_err());
+}
+
+#[test]
+fn settable_reflector() {
+    let k = MachineModel::K;
+    let builder = EnigmaBuilder::for_model(k)
+        .rotors(rotors(k, &["I", "II", "III"]))
+        .reflector(k.reflector("UKW").unwrap())
+        .window_positions([A, A, A]);
+    let mut at_a = builder.build().unwrap();
+    let mut at_g = builder.reflector_position(G).build().unwrap();
+    assert_eq!(G, at_g.reflector_position());
+
+    let plaintext = Alpha::from_string("KEINEBESONDERENEREIGNISSE");
+    let ciphertext = at_g.message(&plaintext);
+    assert_ne!(at_a.message(&plaintext), ciphertext);
+
+    at_g.reset();
+    assert_eq!(plaintext, at_g.message(&ciphertext));
+    assert_eq!(G, at_g.reflector_position());
+}
+
+#[test]
+fn gear_stepping() {
+    let g = MachineModel::G111;
+    let builder = EnigmaBuilder::for_model(g)
+        .rotors(rotors(g, &["I", "II", "V"]))
+        .reflector(g.reflector("UKW").unwrap());
+
+    // Every rotor is at a turnover, so each carries the next, and the left-hand rotor carries
+    // the reflector.
+    let mut enigma = builder.window_positions([S, S, S]).build().unwrap();
+    let mut events = Vec::new();
+    enigma.press_observed(A, &mut |e| events.push(e));
+    assert_eq!(vec![T, T, T], enigma.get_window_positions());
+    assert_eq!(vec![Event::Turnover { slot: 2 },
+                    Event::Step { slot: 2, window: T },
+                    Event::Turnover { slot: 1 },
+                    Event::Step { slot: 1, window: T },
+                    Event::Turnover { slot: 0 },
+                    Event::Step { slot: 0, window: T },
+                    Event::ReflectorStep { position: B }],
+               &events[..7]);
+    assert_eq!(B, enigma.reflector_position());
+    enigma.press(A);
+    assert_eq!(vec![T, T, U], enigma.get_window_positions());
+
+    // A snapshot keeps the reflector's position too.
+    let checkpoint = enigma.snapshot();
+    enigma.reset();
+    assert_eq!(A, enigma.reflector_position());
+    enigma.restore(&checkpoint).unwrap();
+    assert_eq!(B, enigma.reflector_position());
+    assert_eq!(&[T, T, U], checkpoint.window_positions());
+
+    // There is no double step: the middle rotor only moves when carried by the right-hand rotor.
+    let mut enigma = builder.window_positions([A, S, A]).build().unwrap();
+    enigma.press(A);
+    assert_eq!(vec![A, S, B], enigma.get_window_positions());
+}
+
+fn enigma_t(names: &[&str], rings: &str, positions: &str, reflector: Alpha) -> enigma::Enigma {
+    let t = MachineModel::T;
+    let rotors: Vec<_> = names.iter()
+        .zip(Alpha::from_string(rings))
+        .map(|(n, ring)| t.rotor(n).unwrap().with_ring_setting(ring))
+        .collect();
+    EnigmaBuilder::for_model(t)
+        .rotors(rotors)
+        .reflector(t.reflector("UKW").unwrap())
+        .reflector_position(reflector)
+        .window_positions(Alpha::from_string(positions))
+        .build()
+        .unwrap()
+}
+
+// No enciphered Enigma T traffic with its key has been published, so these are not historical
+// vectors. They pin down the entry wheel, the five-notch stepping and the settable reflector, and
+// have been verified against two simulators written apart from this crate, from the published
+// wiring tables, which give the same ciphertexts and final windows.
+#[test]
+fn enigma_t_vectors() {
+    let mut enigma = enigma_t(&["I", "II", "III"], "AAA", "AAA", A);
+    let ciphertext = enigma.message(&[A; 26]);
+    assert_eq!(Alpha::from_string("FWRYLQCQGZRGSVNOZEPVLYXPXQ"), ciphertext);
+    // Rotor III has five turnovers, at E K Q W and Z, so a full turn moves the middle rotor five
+    // times.
+    assert_eq!(Alpha::from_string("AFA"), enigma.get_window_positions());
+
+    let plaintext = Alpha::from_string("DERTIRPITZKREUZERISTBEREIT");
+    let mut enigma = enigma_t(&["VI", "II", "VIII"], "MDK", "QWV", F);
+    let ciphertext = enigma.message(&plaintext);
+    assert_eq!(Alpha::from_string("FRPYEJXMKEHGSWFKOKOIHJJAFL"), ciphertext);
+    assert_eq!(Alpha::from_string("SDV"), enigma.get_window_positions());
+    enigma.reset();
+    assert_eq!(plaintext, enigma.message(&ciphertext));
+
+    // Double stepping, with the middle rotor starting on a turnover.
+    let mut enigma = enigma_t(&["IV", "V", "I"], "AAZ", "VZK", A);
+    let ciphertext = enigma.message(&Alpha::from_string("NOTCHESNOTCHESNOTCHES"));
+    assert_eq!(Alpha::from_string("TRDPLSGTNKLQSOFQYYBOU"), ciphertext);
+    assert_eq!(Alpha::from_string("WFF"), enigma.get_window_positions());
+}
+
+#[test]
+fn enigma_t_parts() {
+    let t = MachineModel::T;
+    assert_eq!(8, t.rotors().len());
+    assert!(t.rotors().iter().all(|w| w.factory().turnovers().len() == 5));
+    assert!(t.settable_reflector());
+    assert!(!t.has_plugboard());
+
+    let i = MachineModel::EnigmaI;
+    let builder = EnigmaBuilder::for_model(t)
+        .rotors(rotors(i, &["I", "II", "III"]))
+        .reflector(t.reflector("UKW").unwrap())
+        .window_positions([A, A, A]);
+    assert_eq!("Rotor was not issued with this model", builder.build().unwrap_err());
+}
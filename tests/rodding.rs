@@ -0,0 +1,69 @@
+#![cfg(feature = "alloc")]
+
+extern crate enigma;
+
+use enigma::Alpha;
+use enigma::EnigmaBuilder;
+use enigma::EntryWheel;
+use enigma::Reflectors;
+use enigma::Rotors;
+use enigma::rodding;
+
+#[test]
+fn finds_right_rotor() {
+    use enigma::Alpha::*;
+
+    let rotors = Rotors::default();
+    let etw = EntryWheel::from_wiring("QWERTZUIOASDFGHJKPYXCVBNML");
+    let mut enigma = EnigmaBuilder::default()
+        .left_rotor(rotors.ii.with_ring_setting(A))
+        .mid_rotor(rotors.v.with_ring_setting(A))
+        .right_rotor(rotors.iii.with_ring_setting(A))
+        .window_positions([K, D, F])
+        .reflector(Reflectors::default().b)
+        .entry_wheel(etw)
+        .build()
+        .unwrap();
+
+    let plaintext = Alpha::from_string("angriffaufdiestadtbeimorgengrauen");
+    let ciphertext = enigma.message(&plaintext);
+
+    let candidates = [rotors.i, rotors.ii, rotors.iii, rotors.iv, rotors.v];
+    let found = rodding::rod(&candidates, Some(&etw), &plaintext, &ciphertext);
+
+    // Rotor III reaches its turnover at V, stepping the middle rotor before the 17th letter. A
+    // double step just before it would also fit, but needs more turnovers.
+    assert!(found.iter().all(|c| (c.rotor, c.offset) == (2, F)));
+    assert_eq!(vec![16], found[0].turnovers);
+}
+
+#[test]
+fn follows_double_step() {
+    use enigma::Alpha::*;
+
+    let rotors = Rotors::default();
+    let etw = EntryWheel::from_wiring("QWERTZUIOASDFGHJKPYXCVBNML");
+    let mut enigma = EnigmaBuilder::default()
+        .left_rotor(rotors.i.with_ring_setting(A))
+        .mid_rotor(rotors.ii.with_ring_setting(A))
+        .right_rotor(rotors.iii.with_ring_setting(C))
+        .window_positions([K, D, H])
+        .reflector(Reflectors::default().b)
+        .entry_wheel(etw)
+        .build()
+        .unwrap();
+
+    let plaintext = Alpha::from_string("ankunftdesgueterzugesausbaselverspaetetsichumzweistunden\
+                                        wegenschneeaufderstrecke");
+    let ciphertext = enigma.message(&plaintext);
+
+    let candidates = [rotors.i, rotors.ii, rotors.iii, rotors.iv, rotors.v];
+    let found = rodding::rod(&candidates, Some(&etw), &plaintext, &ciphertext);
+
+    // Rotor III steps the middle rotor onto its turnover at E before the 15th letter, so the
+    // middle and left rotors step together before the 16th. The middle rotor steps again 26
+    // letters later, and again 26 after that.
+    assert_eq!(2, found[0].rotor);
+    assert_eq!(F, found[0].offset);
+    assert_eq!(vec![14, 15, 40, 66], found[0].turnovers);
+}
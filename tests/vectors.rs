@@ -0,0 +1,27 @@
+#![cfg(feature = "alloc")]
+
+extern crate enigma;
+
+use enigma::MachineModel;
+use enigma::vectors;
+
+const HISTORICAL: &str = include_str!("vectors/historical.txt");
+
+#[test]
+fn historical_messages() {
+    let reports = vectors::run(HISTORICAL).unwrap();
+    let failures: Vec<String> = reports.iter()
+        .filter(|r| !r.passed())
+        .map(|r| r.to_string())
+        .collect();
+    assert!(failures.is_empty(), "\n{}", failures.join("\n"));
+    assert_eq!(5, reports.len());
+}
+
+#[test]
+fn historical_models() {
+    let vectors = vectors::parse(HISTORICAL).unwrap();
+    for model in &[MachineModel::EnigmaI, MachineModel::M3, MachineModel::M4] {
+        assert!(vectors.iter().any(|v| v.model == *model), "No vector for {}", model.name());
+    }
+}
@@ -0,0 +1,49 @@
+//! Run with `wasm-pack test --node -- --features wasm`.
+#![cfg(all(target_arch = "wasm32", feature = "wasm"))]
+
+extern crate enigma;
+extern crate wasm_bindgen_test;
+
+use enigma::wasm::EnigmaBuilder;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+fn builder() -> EnigmaBuilder {
+    EnigmaBuilder::new()
+        .rotors("I II III")
+        .unwrap()
+        .reflector("B")
+        .unwrap()
+        .window_positions("AAA")
+        .unwrap()
+}
+
+#[wasm_bindgen_test]
+fn message() {
+    let mut enigma = builder().build().unwrap();
+    assert_eq!("BDZ GO!", enigma.message("aaa aa!"));
+    assert_eq!("AAF", enigma.get_window_positions());
+
+    enigma.reset();
+    assert_eq!("B", enigma.press("A").unwrap());
+    assert!(enigma.press("AB").is_err());
+}
+
+#[wasm_bindgen_test]
+fn plugboard_and_rings() {
+    let mut enigma = builder()
+        .ring_settings("AAA")
+        .unwrap()
+        .plugboard("AM FI")
+        .unwrap()
+        .build()
+        .unwrap();
+    let ciphertext = enigma.message("HELLO");
+    enigma.reset();
+    assert_eq!("HELLO", enigma.message(&ciphertext));
+}
+
+#[wasm_bindgen_test]
+fn invalid_settings() {
+    assert!(EnigmaBuilder::new().rotors("I II IX").is_err());
+    assert!(builder().ring_settings("AA").unwrap().build().is_err());
+}
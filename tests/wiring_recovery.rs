@@ -0,0 +1,132 @@
+#![cfg(feature = "alloc")]
+
+extern crate enigma;
+
+use enigma::Alpha;
+use enigma::Enigma;
+use enigma::EnigmaBuilder;
+use enigma::Plugboard;
+use enigma::Reflectors;
+use enigma::Rotors;
+use enigma::wiring_recovery::Observation;
+use enigma::wiring_recovery::Problem;
+
+/// A linear congruential generator, so the traffic is the same on every run.
+struct Lcg(u64);
+
+impl Lcg {
+    fn next(&mut self, n: usize) -> usize {
+        self.0 = self.0.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        ((self.0 >> 33) % n as u64) as usize
+    }
+}
+
+fn plugboard() -> Plugboard {
+    use enigma::Alpha::*;
+    Plugboard::new(&[(A, M), (F, I), (N, V), (P, S), (T, U), (W, Z)]).unwrap()
+}
+
+/// Enciphers random letters on rotors I II III, with the right-hand rotor at ring setting C.
+fn observations(count: usize) -> Vec<Observation> {
+    use enigma::Alpha::*;
+
+    let rotors = Rotors::default();
+    let enigma = EnigmaBuilder::default()
+        .left_rotor(rotors.i.with_ring_setting(A))
+        .mid_rotor(rotors.ii.with_ring_setting(A))
+        .right_rotor(rotors.iii.with_ring_setting(C))
+        .window_positions([A, D, K])
+        .reflector(Reflectors::default().b)
+        .plugboard(plugboard())
+        .build()
+        .unwrap();
+    traffic(enigma, count)
+}
+
+/// Enciphers `count` random letters.
+fn traffic(mut enigma: Enigma, count: usize) -> Vec<Observation> {
+    let mut rng = Lcg(7);
+    (0..count)
+        .map(|_| {
+            let plaintext = Alpha::try_from_usize(rng.next(26)).unwrap();
+            let ciphertext = enigma.press(plaintext);
+            Observation {
+                window_positions: enigma.get_window_positions(),
+                plaintext,
+                ciphertext,
+            }
+        })
+        .collect()
+}
+
+fn problem() -> Problem {
+    let rotors = Rotors::default();
+    Problem::new(vec![Some(rotors.i.with_ring_setting(Alpha::A)),
+                      Some(rotors.ii.with_ring_setting(Alpha::A)),
+                      None],
+                 Alpha::C,
+                 Reflectors::default().b)
+            .unwrap()
+            .plugboard(plugboard())
+}
+
+#[test]
+fn recovers_right_rotor() {
+    let observations = observations(200);
+    let problem = problem();
+
+    let solutions = problem.solve(&observations, 10).unwrap();
+    assert!(solutions.exhaustive);
+    assert_eq!(1, solutions.wirings.len());
+    let wiring = solutions.wirings[0];
+    assert_eq!("BDFHJLCPRTXVZNYEIWGAKMUSQO", wiring.to_string());
+
+    let turnovers = problem.turnovers(&observations).unwrap();
+    assert!(turnovers.unknown().is_empty());
+    assert_eq!(vec![Alpha::V], turnovers.letters());
+    let factory = wiring.to_factory(&turnovers).unwrap();
+    assert_eq!(Rotors::default().iii.turnovers(), factory.turnovers());
+}
+
+#[test]
+fn proves_no_wiring_fits() {
+    let mut observations = observations(200);
+    observations[50].ciphertext = observations[50].ciphertext + 1;
+
+    let solutions = problem().solve(&observations, 10).unwrap();
+    assert!(solutions.exhaustive);
+    assert!(solutions.wirings.is_empty());
+}
+
+/// Every rotor has ring setting B. The machine is first checked against the usual vector for
+/// rings BBB, so the traffic is what a real machine would give.
+#[test]
+fn recovers_wiring_with_ring_settings() {
+    use enigma::Alpha::*;
+
+    let rotors = Rotors::default();
+    let mut enigma = EnigmaBuilder::default()
+        .left_rotor(rotors.i.with_ring_setting(B))
+        .mid_rotor(rotors.ii.with_ring_setting(B))
+        .right_rotor(rotors.iii.with_ring_setting(B))
+        .window_positions([A, A, A])
+        .reflector(Reflectors::default().b)
+        .build()
+        .unwrap();
+    assert_eq!(vec![E, W, T, Y, X], enigma.message(&[A, A, A, A, A]));
+
+    enigma.set_window_positions(&[Q, E, J]);
+    let observations = traffic(enigma, 200);
+    let problem = Problem::new(vec![Some(rotors.i.with_ring_setting(B)),
+                                    Some(rotors.ii.with_ring_setting(B)),
+                                    None],
+                               B,
+                               Reflectors::default().b)
+            .unwrap();
+
+    let solutions = problem.solve(&observations, 10).unwrap();
+    assert!(solutions.exhaustive);
+    assert_eq!(1, solutions.wirings.len());
+    assert_eq!("BDFHJLCPRTXVZNYEIWGAKMUSQO", solutions.wirings[0].to_string());
+    assert_eq!(vec![V], problem.turnovers(&observations).unwrap().letters());
+}
@@ -0,0 +1,5 @@
+//! The WebAssembly module for `enigma::wasm`, built with `wasm-pack build wasm`.
+
+extern crate enigma;
+
+pub use enigma::wasm::*;